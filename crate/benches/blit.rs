@@ -0,0 +1,262 @@
+extern crate rust_webpack;
+
+use rust_webpack::pico::*;
+use std::cmp;
+use std::time::{Duration, Instant};
+
+// Times the row span fills and blits against the versions they replaced,
+// copied below as they were. Run with `cargo bench`, it reports the time per
+// call. The old versions only clip some cases, so every shape here is on
+// screen or clipped in a way both handle.
+
+const SIZES: [usize; 2] = [512, 1024];
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    // warm up, then run for about half a second
+    f();
+    let start = Instant::now();
+    let mut runs = 0;
+    while start.elapsed() < Duration::from_millis(500) {
+        f();
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+fn report(name: &str, size: usize, new: Duration, old: Duration) {
+    println!(
+        "{:>29} {:>4}x{:<4} {:>10.1?} {:>10.1?} {:>6.1}x",
+        name,
+        size,
+        size,
+        new,
+        old,
+        old.as_secs_f64() / new.as_secs_f64()
+    );
+}
+
+fn old_rect_fill(x0: i32, y0: i32, x1: i32, y1: i32, c: i32) {
+    let c = wrap_byte(c);
+    let (x0, y0) = offset_point(x0, y0);
+    let (x1, y1) = offset_point(x1, y1);
+    if is_point_on_screen(x0, y0) || is_point_on_screen(x1, y1) {
+        let (x0, y0) = limit_point(x0, y0);
+        let (x1, y1) = limit_point(x1, y1);
+        let (x0, y0, x1, y1) = rect_swap(x0, y0, x1, y1);
+        let target = get_target();
+        let mut screen = screen(target);
+        let width = WIDTH();
+        for y in (y0 as usize)..((y1 as usize) + 1) {
+            let start = y * width + (x0 as usize);
+            let end = y * width + (x1 as usize) + 1;
+            screen[start..end].fill(c);
+        }
+        mark_dirty(target, x0, y0, x1 + 1, y1 + 1);
+    }
+}
+
+fn old_circ_fill(x: i32, y: i32, r: i32, c: i32) {
+    if r <= 0 {
+        pset(x, y, c);
+        return;
+    }
+    if r == 1 {
+        circ(x, y, r, c);
+        pset(x, y, c);
+        return;
+    }
+    init_sides_buffer();
+    circ_with_pixel_func(x, y, r, c, &set_side_pixel);
+
+    let clip_rect = STATE.0.borrow().clip_rect;
+    let yt = cmp::max(clip_rect.t, y - r);
+    let yb = cmp::min(clip_rect.b, y + r + 1);
+    let c = wrap_byte(c);
+    for _y in yt..yb {
+        let _y = _y as usize;
+        let (left, right) = {
+            let state = STATE.0.borrow();
+            (state.sides_buffer_left[_y], state.sides_buffer_right[_y])
+        };
+        let xl = cmp::max(left, clip_rect.l) as usize;
+        let xr = cmp::min(right, clip_rect.r - 1) as usize;
+        hline(xl, xr, _y, c);
+    }
+}
+
+fn old_copy_sprite(
+    source: u8,
+    target: u8,
+    source_x: i32,
+    source_y: i32,
+    target_x: i32,
+    target_y: i32,
+    width: usize,
+    height: usize,
+) {
+    mark_dirty(
+        target,
+        target_x,
+        target_y,
+        target_x + width as i32,
+        target_y + height as i32,
+    );
+    let mut target_screen = screen(target);
+    let source_screen = screen(source);
+    let screen_width = WIDTH();
+    let screen_height = HEIGHT();
+    if screen_width == 0 || screen_height == 0 {
+        return;
+    }
+
+    let source_clip_rect = ClipRect {
+        l: cmp::max(0, source_x),
+        r: cmp::min(screen_width as i32, source_x + width as i32),
+        t: cmp::max(0, source_y),
+        b: cmp::min(screen_height as i32, source_y + height as i32),
+    };
+    let target_clip_rect = ClipRect {
+        l: cmp::max(0, target_x),
+        r: cmp::min(screen_width as i32, target_x + width as i32),
+        t: cmp::max(0, target_y),
+        b: cmp::min(screen_height as i32, target_y + height as i32),
+    };
+    for y in 0..height {
+        let source_pixel_y = source_y + y as i32;
+        let target_pixel_y = target_y + y as i32;
+        if source_pixel_y >= source_clip_rect.t
+            && source_pixel_y < source_clip_rect.b
+            && target_pixel_y >= target_clip_rect.t
+            && target_pixel_y < target_clip_rect.b
+        {
+            for x in 0..width {
+                let source_pixel_x = source_x + x as i32;
+                let target_pixel_x = target_x + x as i32;
+                if source_pixel_x >= source_clip_rect.l
+                    && source_pixel_x < source_clip_rect.r
+                    && target_pixel_x >= target_clip_rect.l
+                    && target_pixel_x < target_clip_rect.r
+                {
+                    let source_pixel_x = source_pixel_x as usize;
+                    let source_pixel_y = source_pixel_y as usize;
+                    let target_pixel_x = target_pixel_x as usize;
+                    let target_pixel_y = target_pixel_y as usize;
+                    target_screen[(target_pixel_y * screen_width + target_pixel_x) as usize] =
+                        source_screen[(source_pixel_y * screen_width + source_pixel_x) as usize];
+                }
+            }
+        }
+    }
+}
+
+fn old_copy_sprite_with_transparency(
+    source: u8,
+    target: u8,
+    source_x: i32,
+    source_y: i32,
+    target_x: i32,
+    target_y: i32,
+    width: usize,
+    height: usize,
+) {
+    mark_dirty(
+        target,
+        target_x,
+        target_y,
+        target_x + width as i32,
+        target_y + height as i32,
+    );
+    let mut target_screen = screen(target);
+    let source_screen = screen(source);
+    let transparency = &(STATE.0.borrow().transparency);
+    let screen_width = WIDTH();
+    let screen_height = HEIGHT();
+    if screen_width == 0 || screen_height == 0 {
+        return;
+    }
+
+    let source_clip_rect = ClipRect {
+        l: cmp::max(0, source_x),
+        r: cmp::min(screen_width as i32, source_x + width as i32),
+        t: cmp::max(0, source_y),
+        b: cmp::min(screen_height as i32, source_y + height as i32),
+    };
+    let target_clip_rect = ClipRect {
+        l: cmp::max(0, target_x),
+        r: cmp::min(screen_width as i32, target_x + width as i32),
+        t: cmp::max(0, target_y),
+        b: cmp::min(screen_height as i32, target_y + height as i32),
+    };
+    for y in 0..height {
+        let source_pixel_y = source_y + y as i32;
+        let target_pixel_y = target_y + y as i32;
+        if source_pixel_y >= source_clip_rect.t
+            && source_pixel_y < source_clip_rect.b
+            && target_pixel_y >= target_clip_rect.t
+            && target_pixel_y < target_clip_rect.b
+        {
+            for x in 0..width {
+                let source_pixel_x = source_x + x as i32;
+                let target_pixel_x = target_x + x as i32;
+                if source_pixel_x >= source_clip_rect.l
+                    && source_pixel_x < source_clip_rect.r
+                    && target_pixel_x >= target_clip_rect.l
+                    && target_pixel_x < target_clip_rect.r
+                {
+                    let source_pixel_x = source_pixel_x as usize;
+                    let source_pixel_y = source_pixel_y as usize;
+                    let target_pixel_x = target_pixel_x as usize;
+                    let target_pixel_y = target_pixel_y as usize;
+                    let source_color =
+                        source_screen[(source_pixel_y * screen_width + source_pixel_x) as usize];
+                    if !transparency[source_color as usize] {
+                        target_screen[(target_pixel_y * screen_width + target_pixel_x) as usize] =
+                            source_color
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    println!(
+        "{:>29} {:>9} {:>10} {:>10} {:>7}",
+        "", "size", "new", "old", "speedup"
+    );
+    for &size in SIZES.iter() {
+        set_dimensions(size, size);
+        set_target(0);
+        for (i, pixel) in screen(1)[..(size * size)].iter_mut().enumerate() {
+            *pixel = (i % 16) as u8;
+        }
+        palt(0, true);
+        let s = size as i32;
+
+        report(
+            "rect_fill",
+            size,
+            time(|| rect_fill(8, 8, s + 8, s + 8, 7)),
+            time(|| old_rect_fill(8, 8, s + 8, s + 8, 7)),
+        );
+        report(
+            "circ_fill",
+            size,
+            time(|| circ_fill(s / 2, s / 2, s / 2 - 1, 7)),
+            time(|| old_circ_fill(s / 2, s / 2, s / 2 - 1, 7)),
+        );
+        report(
+            "copy_sprite",
+            size,
+            time(|| copy_sprite(1, 0, 0, 0, -16, 16, size, size)),
+            time(|| old_copy_sprite(1, 0, 0, 0, -16, 16, size, size)),
+        );
+        report(
+            "copy_sprite_with_transparency",
+            size,
+            time(|| copy_sprite_with_transparency(1, 0, 0, 0, -16, 16, size, size)),
+            time(|| old_copy_sprite_with_transparency(1, 0, 0, 0, -16, 16, size, size)),
+        );
+        palt(0, false);
+    }
+}
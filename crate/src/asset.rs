@@ -0,0 +1,694 @@
+use crate::bytes::*;
+use crate::pico::*;
+use std::cell::RefCell;
+use std::fmt;
+
+// .pico v2 layout, all integers little endian:
+//   "PICO" u16 version
+//   then chunks until the end of the file: [u8; 4] tag, u32 length, data
+// v1 files have no header: u16 width, u16 height, 768 byte palette, pixels.
+
+pub const PICO_MAGIC: &[u8; 4] = b"PICO";
+pub const PICO_VERSION: u16 = 2;
+
+pub const CHUNK_PALETTE: &[u8; 4] = b"PALT";
+pub const CHUNK_PIXELS: &[u8; 4] = b"PIXL";
+pub const CHUNK_PIXELS_RLE: &[u8; 4] = b"PXRL";
+pub const CHUNK_SPRITE_FLAGS: &[u8; 4] = b"FLAG";
+pub const CHUNK_MAP: &[u8; 4] = b"MAP ";
+pub const CHUNK_METADATA: &[u8; 4] = b"META";
+
+pub const SPRITE_COUNT: usize = 256;
+
+#[derive(Debug, PartialEq)]
+pub enum AssetError {
+    TooShort { expected: usize, found: usize },
+    UnsupportedVersion(u16),
+    ChunkOverrun { tag: String, offset: usize },
+    BadChunk { tag: String, message: String },
+    MissingPixels,
+    TooLarge {
+        width: usize,
+        height: usize,
+        max_width: usize,
+        max_height: usize,
+    },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::TooShort { expected, found } => write!(
+                f,
+                "file is truncated, expected at least {} bytes but found {}",
+                expected, found
+            ),
+            AssetError::UnsupportedVersion(version) => {
+                write!(f, "unsupported .pico version {}", version)
+            }
+            AssetError::ChunkOverrun { tag, offset } => write!(
+                f,
+                "chunk '{}' at byte {} runs past the end of the file",
+                tag, offset
+            ),
+            AssetError::BadChunk { tag, message } => write!(f, "chunk '{}': {}", tag, message),
+            AssetError::MissingPixels => write!(f, "file has no pixel chunk"),
+            AssetError::TooLarge {
+                width,
+                height,
+                max_width,
+                max_height,
+            } => write!(
+                f,
+                "{}x{} image does not fit in {}x{}",
+                width, height, max_width, max_height
+            ),
+        }
+    }
+}
+
+fn tag_name(tag: &[u8]) -> String {
+    String::from_utf8_lossy(tag).into_owned()
+}
+
+fn bad_chunk(tag: &[u8], message: &str) -> AssetError {
+    AssetError::BadChunk {
+        tag: tag_name(tag),
+        message: message.to_owned(),
+    }
+}
+
+fn check_len(bytes: &[u8], expected: usize) -> Result<(), AssetError> {
+    if bytes.len() < expected {
+        Err(AssetError::TooShort {
+            expected,
+            found: bytes.len(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn check_size(width: usize, height: usize, max_width: usize, max_height: usize) -> Result<(), AssetError> {
+    if width > max_width || height > max_height {
+        Err(AssetError::TooLarge {
+            width,
+            height,
+            max_width,
+            max_height,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+pub struct Chunk<'a> {
+    pub tag: &'a [u8],
+    pub data: &'a [u8],
+}
+
+pub fn is_v2(bytes: &[u8]) -> bool {
+    bytes.len() >= 4 && &bytes[0..4] == PICO_MAGIC
+}
+
+pub fn read_chunks(bytes: &[u8]) -> Result<Vec<Chunk>, AssetError> {
+    check_len(bytes, 6)?;
+    let version = read_u16_le(bytes, 4);
+    if version != PICO_VERSION {
+        return Err(AssetError::UnsupportedVersion(version));
+    }
+    let mut chunks = vec![];
+    let mut offset = 6;
+    while offset < bytes.len() {
+        check_len(bytes, offset + 8)?;
+        let tag = &bytes[offset..(offset + 4)];
+        let len = read_u32_le(bytes, offset + 4) as usize;
+        let start = offset + 8;
+        if len > bytes.len() - start {
+            return Err(AssetError::ChunkOverrun {
+                tag: tag_name(tag),
+                offset,
+            });
+        }
+        chunks.push(Chunk {
+            tag,
+            data: &bytes[start..(start + len)],
+        });
+        offset = start + len;
+    }
+    Ok(chunks)
+}
+
+// Chunks that carry a u16 width and height in front of width * height bytes.
+pub fn read_sized_chunk<'a>(chunk: &Chunk<'a>) -> Result<(usize, usize, &'a [u8]), AssetError> {
+    if chunk.data.len() < 4 {
+        return Err(bad_chunk(chunk.tag, "missing width and height"));
+    }
+    let width = read_u16_le(chunk.data, 0) as usize;
+    let height = read_u16_le(chunk.data, 2) as usize;
+    let data = &chunk.data[4..];
+    if data.len() != width * height {
+        return Err(bad_chunk(
+            chunk.tag,
+            &format!(
+                "expected {} bytes for {}x{}, found {}",
+                width * height,
+                width,
+                height,
+                data.len()
+            ),
+        ));
+    }
+    Ok((width, height, data))
+}
+
+fn read_palette_chunk(chunk: &Chunk) -> Result<Vec<u8>, AssetError> {
+    if chunk.data.len() % 3 != 0 || chunk.data.len() > NUM_COLORS * 3 {
+        return Err(bad_chunk(
+            chunk.tag,
+            "palette must be at most 256 rgb triples",
+        ));
+    }
+    Ok(chunk.data.to_vec())
+}
+
+fn read_flags_chunk(chunk: &Chunk) -> Result<Vec<u8>, AssetError> {
+    if chunk.data.len() > SPRITE_COUNT {
+        return Err(bad_chunk(chunk.tag, "more than 256 sprite flags"));
+    }
+    Ok(chunk.data.to_vec())
+}
+
+fn read_metadata_chunk(chunk: &Chunk) -> Result<Vec<(String, String)>, AssetError> {
+    let text = std::str::from_utf8(chunk.data)
+        .map_err(|_| bad_chunk(chunk.tag, "metadata is not valid utf-8"))?;
+    let mut metadata = vec![];
+    for line in text.lines().filter(|line| !line.is_empty()) {
+        let mut parts = line.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) => metadata.push((key.to_owned(), value.to_owned())),
+            _ => {
+                return Err(bad_chunk(
+                    chunk.tag,
+                    &format!("'{}' is not a key=value line", line),
+                ))
+            }
+        }
+    }
+    Ok(metadata)
+}
+
+// PXRL holds the same u16 width and height as PIXL followed by runs:
+//   0 to 127    the next n + 1 bytes are copied as they are
+//   128 to 255  the next byte is repeated n - 125 times, 3 to 130
+const RLE_MAX_LITERAL: usize = 128;
+const RLE_MIN_REPEAT: usize = 3;
+const RLE_MAX_REPEAT: usize = 130;
+
+pub fn rle_encode(pixels: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    let mut literal_start = 0;
+    let mut i = 0;
+    let flush = |bytes: &mut Vec<u8>, literals: &[u8]| {
+        for literals in literals.chunks(RLE_MAX_LITERAL) {
+            bytes.push((literals.len() - 1) as u8);
+            bytes.extend_from_slice(literals);
+        }
+    };
+    while i < pixels.len() {
+        let run = pixels[i..]
+            .iter()
+            .take(RLE_MAX_REPEAT)
+            .take_while(|p| **p == pixels[i])
+            .count();
+        if run >= RLE_MIN_REPEAT {
+            flush(&mut bytes, &pixels[literal_start..i]);
+            bytes.push((run + 125) as u8);
+            bytes.push(pixels[i]);
+            i += run;
+            literal_start = i;
+        } else {
+            i += 1;
+        }
+    }
+    flush(&mut bytes, &pixels[literal_start..]);
+    bytes
+}
+
+// Walks the runs for total pixels, checking each against the data and the
+// image size before handing it to emit as (pixels so far, repeated value or
+// literal bytes, count).
+fn rle_walk<F: FnMut(usize, Option<u8>, &[u8], usize)>(
+    tag: &[u8],
+    data: &[u8],
+    total: usize,
+    mut emit: F,
+) -> Result<(), AssetError> {
+    let mut written = 0;
+    let mut offset = 0;
+    while written < total {
+        let control = *data
+            .get(offset)
+            .ok_or_else(|| bad_chunk(tag, "compressed pixels end early"))? as usize;
+        offset += 1;
+        if control < RLE_MAX_LITERAL {
+            let count = control + 1;
+            if offset + count > data.len() || written + count > total {
+                return Err(bad_chunk(tag, "literal run overruns the image"));
+            }
+            emit(written, None, &data[offset..(offset + count)], count);
+            offset += count;
+            written += count;
+        } else {
+            let count = control - 125;
+            let value = *data
+                .get(offset)
+                .ok_or_else(|| bad_chunk(tag, "compressed pixels end early"))?;
+            if written + count > total {
+                return Err(bad_chunk(tag, "repeat run overruns the image"));
+            }
+            emit(written, Some(value), &[], count);
+            offset += 1;
+            written += count;
+        }
+    }
+    if offset != data.len() {
+        return Err(bad_chunk(tag, "data left over after the last pixel"));
+    }
+    Ok(())
+}
+
+// Checks that the runs decode to exactly width x height pixels without
+// writing them anywhere.
+pub fn rle_validate(tag: &[u8], data: &[u8], width: usize, height: usize) -> Result<(), AssetError> {
+    rle_walk(tag, data, width * height, |_, _, _, _| {})
+}
+
+// Decodes width x height pixels into out, whose rows are stride apart, so
+// it can write straight into a screen buffer. Runs before an error are
+// already written, use rle_validate first to leave out untouched.
+pub fn rle_decode_into(
+    tag: &[u8],
+    data: &[u8],
+    width: usize,
+    height: usize,
+    out: &mut [u8],
+    stride: usize,
+) -> Result<(), AssetError> {
+    // copies a run into out one row segment at a time
+    rle_walk(tag, data, width * height, |written, value, literals, count| {
+        let mut done = 0;
+        while done < count {
+            let at = written + done;
+            let (x, y) = (at % width, at / width);
+            let n = (width - x).min(count - done);
+            let start = y * stride + x;
+            match value {
+                Some(value) => out[start..(start + n)].fill(value),
+                None => out[start..(start + n)].copy_from_slice(&literals[done..(done + n)]),
+            }
+            done += n;
+        }
+    })
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Compression {
+    None,
+    Rle,
+}
+
+// Where a file's pixels are, so loading can decode them straight into the
+// target buffer without a copy in between.
+enum PixelSource<'a> {
+    Raw(&'a [u8]),
+    Rle(&'a [u8]),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileMap {
+    pub width: usize,
+    pub height: usize,
+    pub tiles: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PicoAsset {
+    pub width: usize,
+    pub height: usize,
+    pub palette: Option<Vec<u8>>,
+    pub pixels: Vec<u8>,
+    pub sprite_flags: Option<Vec<u8>>,
+    pub map: Option<TileMap>,
+    pub metadata: Vec<(String, String)>,
+}
+
+fn parse_v1(bytes: &[u8]) -> Result<PicoAsset, AssetError> {
+    check_len(bytes, 4)?;
+    let width = read_u16_le(bytes, 0) as usize;
+    let height = read_u16_le(bytes, 2) as usize;
+    check_size(width, height, MAX_WIDTH, MAX_HEIGHT)?;
+    let offset = NUM_COLORS * 3 + 4;
+    check_len(bytes, offset + width * height)?;
+    Ok(PicoAsset {
+        width,
+        height,
+        palette: Some(bytes[4..offset].to_vec()),
+        pixels: bytes[offset..(offset + width * height)].to_vec(),
+        sprite_flags: None,
+        map: None,
+        metadata: vec![],
+    })
+}
+
+// The asset with its pixels left empty, and where to read them from.
+fn parse_parts(bytes: &[u8]) -> Result<(PicoAsset, PixelSource), AssetError> {
+    if !is_v2(bytes) {
+        let mut asset = parse_v1(bytes)?;
+        let offset = NUM_COLORS * 3 + 4;
+        let len = asset.width * asset.height;
+        asset.pixels = vec![];
+        return Ok((asset, PixelSource::Raw(&bytes[offset..(offset + len)])));
+    }
+    let mut palette = None;
+    let mut pixels = None;
+    let mut sprite_flags = None;
+    let mut map = None;
+    let mut metadata = vec![];
+    for chunk in read_chunks(bytes)? {
+        match chunk.tag {
+            t if t == CHUNK_PALETTE => palette = Some(read_palette_chunk(&chunk)?),
+            t if t == CHUNK_PIXELS => {
+                let (width, height, data) = read_sized_chunk(&chunk)?;
+                pixels = Some((width, height, PixelSource::Raw(data)));
+            }
+            t if t == CHUNK_PIXELS_RLE => {
+                if chunk.data.len() < 4 {
+                    return Err(bad_chunk(chunk.tag, "missing width and height"));
+                }
+                let width = read_u16_le(chunk.data, 0) as usize;
+                let height = read_u16_le(chunk.data, 2) as usize;
+                pixels = Some((width, height, PixelSource::Rle(&chunk.data[4..])));
+            }
+            t if t == CHUNK_SPRITE_FLAGS => sprite_flags = Some(read_flags_chunk(&chunk)?),
+            t if t == CHUNK_MAP => {
+                let (width, height, tiles) = read_sized_chunk(&chunk)?;
+                map = Some(TileMap {
+                    width,
+                    height,
+                    tiles: tiles.to_vec(),
+                });
+            }
+            t if t == CHUNK_METADATA => metadata = read_metadata_chunk(&chunk)?,
+            // unknown chunks are skipped so newer files still load
+            _ => {}
+        }
+    }
+    let (width, height, source) = pixels.ok_or(AssetError::MissingPixels)?;
+    check_size(width, height, MAX_WIDTH, MAX_HEIGHT)?;
+    let asset = PicoAsset {
+        width,
+        height,
+        palette,
+        pixels: vec![],
+        sprite_flags,
+        map,
+        metadata,
+    };
+    Ok((asset, source))
+}
+
+pub fn parse_pico(bytes: &[u8]) -> Result<PicoAsset, AssetError> {
+    let (mut asset, source) = parse_parts(bytes)?;
+    asset.pixels = match source {
+        PixelSource::Raw(data) => data.to_vec(),
+        PixelSource::Rle(data) => {
+            let mut pixels = vec![0; asset.width * asset.height];
+            rle_decode_into(
+                CHUNK_PIXELS_RLE,
+                data,
+                asset.width,
+                asset.height,
+                &mut pixels,
+                asset.width,
+            )?;
+            pixels
+        }
+    };
+    Ok(asset)
+}
+
+fn push_chunk(bytes: &mut Vec<u8>, tag: &[u8; 4], data: &[u8]) {
+    bytes.extend_from_slice(tag);
+    push_u32_le(bytes, data.len() as u32);
+    bytes.extend_from_slice(data);
+}
+
+fn sized_chunk_data(width: usize, height: usize, data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16_le(&mut bytes, width as u16);
+    push_u16_le(&mut bytes, height as u16);
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+pub fn write_pico(asset: &PicoAsset) -> Vec<u8> {
+    write_pico_with(asset, Compression::None)
+}
+
+pub fn write_pico_with(asset: &PicoAsset, compression: Compression) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.extend_from_slice(PICO_MAGIC);
+    push_u16_le(&mut bytes, PICO_VERSION);
+    if let Some(palette) = &asset.palette {
+        push_chunk(&mut bytes, CHUNK_PALETTE, palette);
+    }
+    match compression {
+        Compression::None => push_chunk(
+            &mut bytes,
+            CHUNK_PIXELS,
+            &sized_chunk_data(asset.width, asset.height, &asset.pixels),
+        ),
+        Compression::Rle => push_chunk(
+            &mut bytes,
+            CHUNK_PIXELS_RLE,
+            &sized_chunk_data(asset.width, asset.height, &rle_encode(&asset.pixels)),
+        ),
+    }
+    if let Some(flags) = &asset.sprite_flags {
+        push_chunk(&mut bytes, CHUNK_SPRITE_FLAGS, flags);
+    }
+    if let Some(map) = &asset.map {
+        push_chunk(
+            &mut bytes,
+            CHUNK_MAP,
+            &sized_chunk_data(map.width, map.height, &map.tiles),
+        );
+    }
+    if !asset.metadata.is_empty() {
+        let text: String = asset
+            .metadata
+            .iter()
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect();
+        push_chunk(&mut bytes, CHUNK_METADATA, text.as_bytes());
+    }
+    bytes
+}
+
+pub struct SpriteFlags(pub RefCell<[u8; SPRITE_COUNT]>);
+unsafe impl Sync for SpriteFlags {}
+
+pub struct TileMapContainer(pub RefCell<Option<TileMap>>);
+unsafe impl Sync for TileMapContainer {}
+
+pub static SPRITE_FLAGS: SpriteFlags = SpriteFlags(RefCell::new([0; SPRITE_COUNT]));
+pub static TILE_MAP: TileMapContainer = TileMapContainer(RefCell::new(None));
+
+pub fn fget(sprite: u8) -> u8 {
+    SPRITE_FLAGS.0.borrow()[sprite as usize]
+}
+
+pub fn fset(sprite: u8, flags: u8) {
+    SPRITE_FLAGS.0.borrow_mut()[sprite as usize] = flags;
+}
+
+pub fn mget(x: i32, y: i32) -> u8 {
+    match TILE_MAP.0.borrow().as_ref() {
+        Some(map) if x >= 0 && y >= 0 && (x as usize) < map.width && (y as usize) < map.height => {
+            map.tiles[(y as usize) * map.width + (x as usize)]
+        }
+        _ => 0,
+    }
+}
+
+pub fn mset(x: i32, y: i32, tile: u8) {
+    if let Some(map) = TILE_MAP.0.borrow_mut().as_mut() {
+        if x >= 0 && y >= 0 && (x as usize) < map.width && (y as usize) < map.height {
+            map.tiles[(y as usize) * map.width + (x as usize)] = tile;
+        }
+    }
+}
+
+pub fn set_sprite_flags(flags: &[u8]) {
+    let mut sprite_flags = SPRITE_FLAGS.0.borrow_mut();
+    sprite_flags.fill(0);
+    sprite_flags[..flags.len()].copy_from_slice(flags);
+}
+
+pub fn set_palette(palette: &[u8]) {
+    PALETTE.0.borrow_mut()[..palette.len()].copy_from_slice(palette);
+}
+
+// Copies width x height pixels into the top left of a target, keeping the
+// target's row stride.
+pub fn blit_pixels(target: u8, width: usize, height: usize, pixels: &[u8]) -> Result<(), AssetError> {
+    let buf_width = WIDTH();
+    check_size(width, height, buf_width, HEIGHT())?;
+    {
+        let mut buf = screen(target);
+        for i in 0..height {
+            buf[(buf_width * i)..(buf_width * i + width)]
+                .copy_from_slice(&pixels[(width * i)..(width * (i + 1))]);
+        }
+    }
+    mark_dirty(target, 0, 0, width as i32, height as i32);
+    Ok(())
+}
+
+// Loads the pixels into buffer 3 and installs the palette, sprite flags and
+// map when the file has them. Nothing is changed when decoding fails.
+pub fn load_spritesheet(bytes: &[u8]) -> Result<(), AssetError> {
+    let (asset, source) = parse_parts(bytes)?;
+    match source {
+        PixelSource::Raw(pixels) => blit_pixels(3, asset.width, asset.height, pixels)?,
+        PixelSource::Rle(data) => {
+            let buf_width = WIDTH();
+            check_size(asset.width, asset.height, buf_width, HEIGHT())?;
+            rle_validate(CHUNK_PIXELS_RLE, data, asset.width, asset.height)?;
+            rle_decode_into(
+                CHUNK_PIXELS_RLE,
+                data,
+                asset.width,
+                asset.height,
+                &mut screen(3)[..],
+                buf_width,
+            )?;
+            mark_dirty(3, 0, 0, asset.width as i32, asset.height as i32);
+        }
+    }
+    if let Some(palette) = &asset.palette {
+        set_palette(palette);
+    }
+    if let Some(flags) = &asset.sprite_flags {
+        set_sprite_flags(flags);
+    }
+    if let Some(map) = asset.map {
+        *TILE_MAP.0.borrow_mut() = Some(map);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v1(width: u16, height: u16, pixels: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![];
+        push_u16_le(&mut bytes, width);
+        push_u16_le(&mut bytes, height);
+        bytes.extend((0..(NUM_COLORS * 3)).map(|i| i as u8));
+        bytes.extend_from_slice(pixels);
+        bytes
+    }
+
+    fn v2(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut bytes = PICO_MAGIC.to_vec();
+        push_u16_le(&mut bytes, PICO_VERSION);
+        for (tag, data) in chunks {
+            push_chunk(&mut bytes, tag, data);
+        }
+        bytes
+    }
+
+    #[test]
+    fn reads_v1_files() {
+        let asset = parse_pico(&v1(2, 2, &[1, 2, 3, 4])).unwrap();
+        assert_eq!((asset.width, asset.height), (2, 2));
+        assert_eq!(asset.pixels, vec![1, 2, 3, 4]);
+        assert_eq!(asset.palette.unwrap()[..4], [0, 1, 2, 3]);
+        assert_eq!(asset.sprite_flags, None);
+    }
+
+    #[test]
+    fn rejects_truncated_v1_files() {
+        assert_eq!(
+            parse_pico(&[2, 0]),
+            Err(AssetError::TooShort {
+                expected: 4,
+                found: 2
+            })
+        );
+        let bytes = v1(2, 2, &[1, 2, 3]);
+        assert_eq!(
+            parse_pico(&bytes),
+            Err(AssetError::TooShort {
+                expected: bytes.len() + 1,
+                found: bytes.len()
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_v2_headers() {
+        assert_eq!(
+            parse_pico(b"PICO\x02"),
+            Err(AssetError::TooShort {
+                expected: 6,
+                found: 5
+            })
+        );
+        let mut bytes = v2(&[]);
+        bytes.extend_from_slice(b"PIXL\x04\x00");
+        assert_eq!(
+            parse_pico(&bytes),
+            Err(AssetError::TooShort {
+                expected: 14,
+                found: 12
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        assert_eq!(
+            parse_pico(b"PICO\x09\x00"),
+            Err(AssetError::UnsupportedVersion(9))
+        );
+    }
+
+    #[test]
+    fn rejects_chunks_running_past_the_end() {
+        let mut bytes = v2(&[(CHUNK_PIXELS, &[1, 0, 1, 0, 5])]);
+        bytes[10] = 100;
+        assert_eq!(
+            parse_pico(&bytes),
+            Err(AssetError::ChunkOverrun {
+                tag: "PIXL".to_owned(),
+                offset: 6
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_files_without_pixels() {
+        let bytes = v2(&[(CHUNK_PALETTE, &[1, 2, 3]), (CHUNK_SPRITE_FLAGS, &[1])]);
+        assert_eq!(parse_pico(&bytes), Err(AssetError::MissingPixels));
+    }
+
+    #[test]
+    fn skips_unknown_chunks() {
+        let bytes = v2(&[(b"NEW!", &[9, 9]), (CHUNK_PIXELS, &[1, 0, 1, 0, 5])]);
+        assert_eq!(parse_pico(&bytes).unwrap().pixels, vec![5]);
+    }
+}
@@ -0,0 +1,387 @@
+use crate::font::*;
+use std::fmt;
+use std::str;
+
+#[derive(Debug, PartialEq)]
+pub enum BdfError {
+    NotUtf8,
+    MissingStartFont,
+    MissingBoundingBox,
+    UnexpectedEnd,
+    InvalidLine { line: usize, message: String },
+    GlyphTooWide { line: usize, width: i32 },
+}
+
+impl fmt::Display for BdfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BdfError::NotUtf8 => write!(f, "bdf file is not valid utf-8"),
+            BdfError::MissingStartFont => write!(f, "bdf file does not start with STARTFONT"),
+            BdfError::MissingBoundingBox => write!(f, "bdf file has no FONTBOUNDINGBOX"),
+            BdfError::UnexpectedEnd => write!(f, "bdf file ended inside a glyph"),
+            BdfError::InvalidLine { line, message } => write!(f, "line {}: {}", line, message),
+            BdfError::GlyphTooWide { line, width } => write!(
+                f,
+                "line {}: glyph is {} pixels wide, at most 32 are supported",
+                line, width
+            ),
+        }
+    }
+}
+
+fn invalid(line: usize, message: &str) -> BdfError {
+    BdfError::InvalidLine {
+        line,
+        message: message.to_owned(),
+    }
+}
+
+fn parse_ints(line: usize, args: &[&str], count: usize) -> Result<Vec<i32>, BdfError> {
+    if args.len() < count {
+        return Err(invalid(
+            line,
+            &format!("expected {} numbers, found {}", count, args.len()),
+        ));
+    }
+    args[..count]
+        .iter()
+        .map(|arg| {
+            arg.parse::<i32>()
+                .map_err(|_| invalid(line, &format!("'{}' is not a number", arg)))
+        })
+        .collect()
+}
+
+struct BoundingBox {
+    width: i32,
+    height: i32,
+    x_offset: i32,
+    y_offset: i32,
+}
+
+fn parse_bounding_box(line: usize, args: &[&str]) -> Result<BoundingBox, BdfError> {
+    let values = parse_ints(line, args, 4)?;
+    if values[0] < 0 || values[1] < 0 {
+        return Err(invalid(line, "bounding box size is negative"));
+    }
+    Ok(BoundingBox {
+        width: values[0],
+        height: values[1],
+        x_offset: values[2],
+        y_offset: values[3],
+    })
+}
+
+// BDF rows are hex with the leftmost pixel in the most significant bit,
+// padded out to whole bytes.
+fn parse_bitmap_row(line: usize, hex: &str, width: i32) -> Result<u32, BdfError> {
+    let bytes = ((width + 7) / 8) as usize;
+    if hex.len() < bytes * 2 {
+        return Err(invalid(
+            line,
+            &format!("bitmap row '{}' is shorter than the glyph width", hex),
+        ));
+    }
+    let value = u64::from_str_radix(&hex[..(bytes * 2)], 16)
+        .map_err(|_| invalid(line, &format!("'{}' is not a hex bitmap row", hex)))?;
+    let bits = bytes * 8;
+    let mut row = 0;
+    for x in 0..(width as usize) {
+        if (value >> (bits - 1 - x)) & 1 == 1 {
+            row |= 1 << x;
+        }
+    }
+    Ok(row)
+}
+
+struct PendingGlyph {
+    encoding: Option<char>,
+    advance: Option<i32>,
+    bbx: Option<BoundingBox>,
+}
+
+pub fn parse_bdf(bytes: &[u8]) -> Result<Font, BdfError> {
+    let text = str::from_utf8(bytes).map_err(|_| BdfError::NotUtf8)?;
+    let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l.trim()));
+
+    match lines.next() {
+        Some((_, l)) if l.starts_with("STARTFONT") => {}
+        _ => return Err(BdfError::MissingStartFont),
+    }
+
+    let mut bounding_box: Option<BoundingBox> = None;
+    let mut ascent: Option<i32> = None;
+    let mut descent: Option<i32> = None;
+    let mut font_advance: Option<i32> = None;
+    let mut default_char: Option<char> = None;
+    let mut glyphs: Vec<(char, Glyph)> = vec![];
+    let mut pending: Option<PendingGlyph> = None;
+
+    while let Some((line, content)) = lines.next() {
+        let mut parts = content.split_whitespace();
+        let keyword = match parts.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = parts.collect();
+        match keyword {
+            "COMMENT" => {}
+            "FONTBOUNDINGBOX" => bounding_box = Some(parse_bounding_box(line, &args)?),
+            "FONT_ASCENT" => ascent = Some(parse_ints(line, &args, 1)?[0]),
+            "FONT_DESCENT" => descent = Some(parse_ints(line, &args, 1)?[0]),
+            "DEFAULT_CHAR" => {
+                default_char = std::char::from_u32(parse_ints(line, &args, 1)?[0] as u32)
+            }
+            "STARTCHAR" => {
+                if pending.is_some() {
+                    return Err(invalid(line, "STARTCHAR inside another glyph"));
+                }
+                pending = Some(PendingGlyph {
+                    encoding: None,
+                    advance: None,
+                    bbx: None,
+                });
+            }
+            "ENCODING" => {
+                let glyph = pending
+                    .as_mut()
+                    .ok_or_else(|| invalid(line, "ENCODING outside of a glyph"))?;
+                let code = parse_ints(line, &args, 1)?[0];
+                // -1 means the glyph has no standard encoding, skip it
+                glyph.encoding = if code < 0 {
+                    None
+                } else {
+                    Some(
+                        std::char::from_u32(code as u32)
+                            .ok_or_else(|| invalid(line, "encoding is not a valid character"))?,
+                    )
+                };
+            }
+            "DWIDTH" => {
+                let advance = parse_ints(line, &args, 1)?[0];
+                match pending.as_mut() {
+                    Some(glyph) => glyph.advance = Some(advance),
+                    None => font_advance = Some(advance),
+                }
+            }
+            "BBX" => {
+                let glyph = pending
+                    .as_mut()
+                    .ok_or_else(|| invalid(line, "BBX outside of a glyph"))?;
+                let bbx = parse_bounding_box(line, &args)?;
+                if bbx.width > MAX_GLYPH_WIDTH {
+                    return Err(BdfError::GlyphTooWide {
+                        line,
+                        width: bbx.width,
+                    });
+                }
+                glyph.bbx = Some(bbx);
+            }
+            "BITMAP" => {
+                let glyph = pending
+                    .take()
+                    .ok_or_else(|| invalid(line, "BITMAP outside of a glyph"))?;
+                let font_box = bounding_box
+                    .as_ref()
+                    .ok_or(BdfError::MissingBoundingBox)?;
+                let bbx = glyph.bbx.unwrap_or(BoundingBox {
+                    width: font_box.width,
+                    height: font_box.height,
+                    x_offset: font_box.x_offset,
+                    y_offset: font_box.y_offset,
+                });
+                if bbx.width > MAX_GLYPH_WIDTH {
+                    return Err(BdfError::GlyphTooWide {
+                        line,
+                        width: bbx.width,
+                    });
+                }
+                let mut rows = vec![];
+                loop {
+                    let (row_line, row) = lines.next().ok_or(BdfError::UnexpectedEnd)?;
+                    if row == "ENDCHAR" {
+                        break;
+                    }
+                    if rows.len() as i32 >= bbx.height {
+                        return Err(invalid(row_line, "more bitmap rows than the BBX height"));
+                    }
+                    rows.push(parse_bitmap_row(row_line, row, bbx.width)?);
+                }
+                if (rows.len() as i32) < bbx.height {
+                    return Err(invalid(
+                        line,
+                        &format!(
+                            "expected {} bitmap rows, found {}",
+                            bbx.height,
+                            rows.len()
+                        ),
+                    ));
+                }
+                if let Some(character) = glyph.encoding {
+                    let advance = glyph
+                        .advance
+                        .or(font_advance)
+                        .unwrap_or(font_box.width);
+                    glyphs.push((
+                        character,
+                        Glyph {
+                            width: bbx.width,
+                            height: bbx.height,
+                            x_offset: bbx.x_offset,
+                            // relative to the baseline for now, moved to
+                            // the top of the line once the ascent is known
+                            y_offset: -(bbx.y_offset + bbx.height),
+                            advance,
+                            rows,
+                        },
+                    ));
+                }
+            }
+            "ENDCHAR" => return Err(invalid(line, "ENDCHAR without BITMAP")),
+            "ENDFONT" => break,
+            _ => {}
+        }
+    }
+    if pending.is_some() {
+        return Err(BdfError::UnexpectedEnd);
+    }
+
+    let font_box = bounding_box.ok_or(BdfError::MissingBoundingBox)?;
+    let ascent = ascent.unwrap_or(font_box.height + font_box.y_offset);
+    let descent = descent.unwrap_or(-font_box.y_offset);
+    let default_advance = font_advance.unwrap_or(font_box.width);
+    let mut font = Font::new(ascent + descent, ascent, default_advance);
+    for (character, mut glyph) in glyphs {
+        glyph.y_offset += ascent;
+        font.add_glyph(character, glyph);
+    }
+    if let Some(space) = font.glyphs.get(&' ') {
+        font.default_advance = space.advance;
+        font.tab_width = space.advance * 4;
+    }
+    if let Some(default_char) = default_char {
+        font.missing = font.glyphs.get(&default_char).cloned();
+    }
+    Ok(font)
+}
+
+pub fn load_bdf_font(bytes: &[u8]) -> Result<FontHandle, BdfError> {
+    Ok(register_font(parse_bdf(bytes)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &str = "STARTFONT 2.1
+FONT tiny
+FONTBOUNDINGBOX 4 5 0 -1
+FONT_ASCENT 4
+FONT_DESCENT 1
+CHARS 2
+STARTCHAR space
+ENCODING 32
+DWIDTH 3 0
+BBX 1 1 0 0
+BITMAP
+00
+ENDCHAR
+STARTCHAR A
+ENCODING 65
+DWIDTH 5 0
+BBX 3 4 0 0
+BITMAP
+40
+A0
+E0
+A0
+ENDCHAR
+ENDFONT
+";
+
+    fn with_glyph(glyph: &str) -> String {
+        format!(
+            "STARTFONT 2.1\nFONTBOUNDINGBOX 4 5 0 -1\nSTARTCHAR x\nENCODING 120\n{}",
+            glyph
+        )
+    }
+
+    #[test]
+    fn parses_a_small_font() {
+        let font = parse_bdf(FONT.as_bytes()).unwrap();
+        assert_eq!((font.line_height, font.baseline), (5, 4));
+        assert_eq!((font.default_advance, font.tab_width), (3, 12));
+        let a = font.glyph('A').unwrap();
+        assert_eq!((a.width, a.height, a.y_offset, a.advance), (3, 4, 0, 5));
+        assert_eq!(a.rows, vec![0b010, 0b101, 0b111, 0b101]);
+    }
+
+    #[test]
+    fn missing_bitmap_is_an_error() {
+        let bdf = with_glyph("BBX 3 2 0 0\nENDCHAR\nENDFONT\n");
+        assert_eq!(
+            parse_bdf(bdf.as_bytes()).err(),
+            Some(invalid(6, "ENDCHAR without BITMAP"))
+        );
+        let bdf = with_glyph("BBX 3 2 0 0\n");
+        assert_eq!(
+            parse_bdf(bdf.as_bytes()).err(),
+            Some(BdfError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn bad_bbx_is_an_error() {
+        let bdf = with_glyph("BBX 3 two 0 0\nBITMAP\n00\nENDCHAR\n");
+        assert_eq!(
+            parse_bdf(bdf.as_bytes()).err(),
+            Some(invalid(5, "'two' is not a number"))
+        );
+        let bdf = with_glyph("BBX 3 -2 0 0\nBITMAP\nENDCHAR\n");
+        assert_eq!(
+            parse_bdf(bdf.as_bytes()).err(),
+            Some(invalid(5, "bounding box size is negative"))
+        );
+        let bdf = with_glyph("BBX 40 1 0 0\nBITMAP\n0000000000\nENDCHAR\n");
+        assert_eq!(
+            parse_bdf(bdf.as_bytes()).err(),
+            Some(BdfError::GlyphTooWide { line: 5, width: 40 })
+        );
+    }
+
+    #[test]
+    fn short_bitmap_rows_are_errors() {
+        let bdf = with_glyph("BBX 12 1 0 0\nBITMAP\nFF\nENDCHAR\n");
+        assert_eq!(
+            parse_bdf(bdf.as_bytes()).err(),
+            Some(invalid(7, "bitmap row 'FF' is shorter than the glyph width"))
+        );
+        let bdf = with_glyph("BBX 3 2 0 0\nBITMAP\nE0\nENDCHAR\n");
+        assert_eq!(
+            parse_bdf(bdf.as_bytes()).err(),
+            Some(invalid(6, "expected 2 bitmap rows, found 1"))
+        );
+    }
+
+    #[test]
+    fn non_hex_bitmap_rows_are_errors() {
+        let bdf = with_glyph("BBX 3 1 0 0\nBITMAP\nZZ\nENDCHAR\n");
+        assert_eq!(
+            parse_bdf(bdf.as_bytes()).err(),
+            Some(invalid(7, "'ZZ' is not a hex bitmap row"))
+        );
+    }
+
+    #[test]
+    fn header_errors() {
+        assert_eq!(parse_bdf(&[0xff, 0xfe]).err(), Some(BdfError::NotUtf8));
+        assert_eq!(
+            parse_bdf(b"FONT tiny\n").err(),
+            Some(BdfError::MissingStartFont)
+        );
+        assert_eq!(
+            parse_bdf(b"STARTFONT 2.1\nENDFONT\n").err(),
+            Some(BdfError::MissingBoundingBox)
+        );
+    }
+}
@@ -0,0 +1,97 @@
+extern crate rust_webpack;
+
+use rust_webpack::asset::{parse_pico, write_pico_with, Compression};
+use rust_webpack::convert::*;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "usage: png_to_pico <input.png> [output.pico] [--palette <file>] [--quantize <colors>] [--transparent <index>] [--compress]
+
+  --palette <file>      map to the palette of a .pico file, or a raw file of rgb triples
+  --pico8               map to the 16 color PICO-8 palette
+  --quantize <colors>   build a palette of at most this many colors
+  --transparent <index> write this index for pixels with alpha below 128
+  --compress            run length encode the pixels
+
+Without a palette option indexed pngs keep their palette and other pngs are
+quantized to 256 colors. A .pico input is written back out as it is, so
+`png_to_pico face.pico --compress` repacks a file in place.";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn read(path: &str) -> Vec<u8> {
+    fs::read(path).unwrap_or_else(|err| fail(&format!("could not read {}: {}", path, err)))
+}
+
+fn read_palette(path: &str) -> Vec<u8> {
+    let bytes = read(path);
+    match parse_pico(&bytes) {
+        Ok(asset) => asset
+            .palette
+            .unwrap_or_else(|| fail(&format!("{} has no palette", path))),
+        Err(_) => bytes,
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut positional = vec![];
+    let mut options = ConvertOptions::default();
+    let mut i = 0;
+    while i < args.len() {
+        let value = || {
+            args.get(i + 1)
+                .map(|v| v.as_str())
+                .unwrap_or_else(|| fail(USAGE))
+        };
+        match args[i].as_str() {
+            "--palette" => {
+                options.palette = PaletteSource::Fixed(read_palette(value()));
+                i += 1;
+            }
+            "--pico8" => options.palette = PaletteSource::Fixed(pico8_palette()),
+            "--quantize" => {
+                let colors = value().parse().unwrap_or_else(|_| fail(USAGE));
+                options.palette = PaletteSource::Quantize(colors);
+                i += 1;
+            }
+            "--transparent" => {
+                options.transparent_index = Some(value().parse().unwrap_or_else(|_| fail(USAGE)));
+                i += 1;
+            }
+            "--compress" => options.compression = Compression::Rle,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            arg => positional.push(arg.to_owned()),
+        }
+        i += 1;
+    }
+    let input = match positional.get(0) {
+        Some(input) => input.clone(),
+        None => fail(USAGE),
+    };
+    let output = match positional.get(1) {
+        Some(output) => output.clone(),
+        None => Path::new(&input)
+            .with_extension("pico")
+            .to_string_lossy()
+            .into_owned(),
+    };
+    let pico = if input.ends_with(".pico") {
+        let asset = parse_pico(&read(&input))
+            .unwrap_or_else(|err| fail(&format!("{}: {}", input, err)));
+        write_pico_with(&asset, options.compression)
+    } else {
+        png_to_pico(&read(&input), &options)
+            .unwrap_or_else(|err| fail(&format!("{}: {}", input, err)))
+    };
+    fs::write(&output, pico)
+        .unwrap_or_else(|err| fail(&format!("could not write {}: {}", output, err)));
+}
@@ -0,0 +1,67 @@
+extern crate rust_webpack;
+
+use rust_webpack::recorder;
+use rust_webpack::*;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "usage: render_gallery [output dir] [--frames <count>] [--frame-time <ms>]
+
+Renders every sketch in the gallery to <output dir>/<index>-<sketch url>.gif";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut output = String::from("gallery");
+    let mut frames = 120;
+    let mut frame_time = 1000.0 / 60.0;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--frames" => {
+                frames = args
+                    .get(i + 1)
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or_else(|| fail(USAGE));
+                i += 1;
+            }
+            "--frame-time" => {
+                frame_time = args
+                    .get(i + 1)
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or_else(|| fail(USAGE));
+                i += 1;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            arg => output = arg.to_owned(),
+        }
+        i += 1;
+    }
+    fs::create_dir_all(&output)
+        .unwrap_or_else(|err| fail(&format!("could not create {}: {}", output, err)));
+
+    for index in 0..get_sketch_count() {
+        let url = get_sketch_url(index).unwrap_or_else(|| index.to_string());
+        init(index);
+        // the recorder directly, the bindgen wrappers build JsValues for errors
+        recorder::start_recording(frames);
+        for _ in 0..frames {
+            update(frame_time);
+        }
+        let gif = recorder::stop_recording()
+            .unwrap_or_else(|err| fail(&format!("could not encode {}: {}", url, err)));
+        let path = Path::new(&output).join(format!("{:02}-{}.gif", index, url));
+        fs::write(&path, gif)
+            .unwrap_or_else(|err| fail(&format!("could not write {}: {}", path.display(), err)));
+        println!("{}", path.display());
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+
+// Little endian helpers shared by the .pico, display list and document
+// formats.
+
+pub fn read_u16_le(bytes: &[u8], offset: usize) -> u16 {
+    (bytes[offset] as u16) | ((bytes[offset + 1] as u16) << 8)
+}
+
+pub fn read_u32_le(bytes: &[u8], offset: usize) -> u32 {
+    (bytes[offset] as u32)
+        | ((bytes[offset + 1] as u32) << 8)
+        | ((bytes[offset + 2] as u32) << 16)
+        | ((bytes[offset + 3] as u32) << 24)
+}
+
+pub fn push_u16_le(bytes: &mut Vec<u8>, v: u16) {
+    bytes.extend_from_slice(&[v as u8, (v >> 8) as u8]);
+}
+
+pub fn push_u32_le(bytes: &mut Vec<u8>, v: u32) {
+    bytes.extend_from_slice(&[v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8]);
+}
+
+pub fn push_i32s(bytes: &mut Vec<u8>, values: &[i32]) {
+    for v in values {
+        push_u32_le(bytes, *v as u32);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ReadError {
+    // len more bytes were needed at offset
+    UnexpectedEnd { offset: usize, len: usize },
+    BadMagic,
+    Invalid(&'static str),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::UnexpectedEnd { offset, len } => {
+                write!(f, "expected {} more bytes at offset {}", len, offset)
+            }
+            ReadError::BadMagic => write!(f, "unrecognized file type"),
+            ReadError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes, pos: 0 }
+    }
+
+    // Lengths come from the data itself, so the end is checked for overflow
+    // before slicing.
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], ReadError> {
+        let end = match self.pos.checked_add(len) {
+            Some(end) if end <= self.bytes.len() => end,
+            _ => {
+                return Err(ReadError::UnexpectedEnd {
+                    offset: self.pos,
+                    len,
+                })
+            }
+        };
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, ReadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, ReadError> {
+        Ok(read_u32_le(self.take(4)?, 0))
+    }
+
+    pub fn i32(&mut self) -> Result<i32, ReadError> {
+        Ok(self.u32()? as i32)
+    }
+}
@@ -0,0 +1,274 @@
+use crate::pico::*;
+use std::cell::RefCell;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ColorMetric {
+    // squared distance in rgb, fast but favours greens and blues oddly
+    Rgb,
+    // CIE76 distance in Lab, closer to what looks alike
+    Lab,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+fn srgb_to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn lab_f(t: f32) -> f32 {
+    if t > 216.0 / 24389.0 {
+        t.cbrt()
+    } else {
+        (24389.0 / 27.0 * t + 16.0) / 116.0
+    }
+}
+
+impl Lab {
+    // sRGB with a D65 white point
+    pub fn from_rgb(rgb: &[u8]) -> Lab {
+        let r = srgb_to_linear(rgb[0]);
+        let g = srgb_to_linear(rgb[1]);
+        let b = srgb_to_linear(rgb[2]);
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.950_47;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.088_83;
+        let (fx, fy, fz) = (lab_f(x), lab_f(y), lab_f(z));
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    pub fn distance(&self, other: &Lab) -> f32 {
+        let dl = self.l - other.l;
+        let da = self.a - other.a;
+        let db = self.b - other.b;
+        dl * dl + da * da + db * db
+    }
+}
+
+pub fn rgb_distance(a: &[u8], b: &[u8]) -> u32 {
+    let dr = a[0] as i32 - b[0] as i32;
+    let dg = a[1] as i32 - b[1] as i32;
+    let db = a[2] as i32 - b[2] as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+// Index of the closest of the rgb triples in palette, the first one wins
+// ties.
+pub fn nearest_index(palette: &[u8], rgb: &[u8], metric: ColorMetric) -> u8 {
+    let mut best = 0;
+    match metric {
+        ColorMetric::Rgb => {
+            let mut best_distance = u32::max_value();
+            for (i, color) in palette.chunks(3).enumerate() {
+                let distance = rgb_distance(color, rgb);
+                if distance < best_distance {
+                    best = i;
+                    best_distance = distance;
+                }
+            }
+        }
+        ColorMetric::Lab => {
+            let labs: Vec<Lab> = palette.chunks(3).map(Lab::from_rgb).collect();
+            best = nearest_lab(&labs, &Lab::from_rgb(rgb));
+        }
+    }
+    best as u8
+}
+
+pub fn nearest_lab(labs: &[Lab], target: &Lab) -> usize {
+    let mut best = 0;
+    let mut best_distance = std::f32::MAX;
+    for (i, lab) in labs.iter().enumerate() {
+        let distance = lab.distance(target);
+        if distance < best_distance {
+            best = i;
+            best_distance = distance;
+        }
+    }
+    best
+}
+
+// Looks colors up by their top 5 bits per channel. It is built from a copy
+// of PALETTE and rebuilt whenever PALETTE no longer matches that copy.
+struct InverseColormap {
+    palette: Vec<u8>,
+    metric: ColorMetric,
+    table: Vec<u8>,
+}
+
+const INVERSE_BITS: usize = 5;
+const INVERSE_SIZE: usize = 1 << INVERSE_BITS;
+
+impl InverseColormap {
+    fn build(palette: Vec<u8>, metric: ColorMetric) -> InverseColormap {
+        let mut table = vec![0; INVERSE_SIZE * INVERSE_SIZE * INVERSE_SIZE];
+        let labs: Vec<Lab> = palette.chunks(3).map(Lab::from_rgb).collect();
+        // match against the middle of each cell rather than its low corner
+        let level = |i: usize| ((i << (8 - INVERSE_BITS)) | (1 << (7 - INVERSE_BITS))) as u8;
+        for r in 0..INVERSE_SIZE {
+            for g in 0..INVERSE_SIZE {
+                for b in 0..INVERSE_SIZE {
+                    let rgb = [level(r), level(g), level(b)];
+                    table[(r * INVERSE_SIZE + g) * INVERSE_SIZE + b] = match metric {
+                        ColorMetric::Rgb => nearest_index(&palette, &rgb, metric),
+                        ColorMetric::Lab => nearest_lab(&labs, &Lab::from_rgb(&rgb)) as u8,
+                    };
+                }
+            }
+        }
+        InverseColormap {
+            palette,
+            metric,
+            table,
+        }
+    }
+
+    fn lookup(&self, rgb: &[u8]) -> u8 {
+        let shift = 8 - INVERSE_BITS;
+        let (r, g, b) = (
+            (rgb[0] >> shift) as usize,
+            (rgb[1] >> shift) as usize,
+            (rgb[2] >> shift) as usize,
+        );
+        self.table[(r * INVERSE_SIZE + g) * INVERSE_SIZE + b]
+    }
+}
+
+struct InverseColormapCache(RefCell<Option<InverseColormap>>);
+unsafe impl Sync for InverseColormapCache {}
+
+static INVERSE_COLORMAP: InverseColormapCache = InverseColormapCache(RefCell::new(None));
+
+// Nearest of the first count PALETTE entries, exact but slow.
+pub fn nearest_color(rgb: &[u8], count: usize, metric: ColorMetric) -> u8 {
+    let palette = PALETTE.0.borrow();
+    nearest_index(&palette[..(count.min(NUM_COLORS).max(1) * 3)], rgb, metric)
+}
+
+// Nearest of the first count PALETTE entries through the cached inverse
+// colormap. Close colors can land on a neighbour of the exact match.
+pub fn nearest_color_cached(rgb: &[u8], count: usize, metric: ColorMetric) -> u8 {
+    let palette = PALETTE.0.borrow();
+    let current = &palette[..(count.min(NUM_COLORS).max(1) * 3)];
+    let mut cache = INVERSE_COLORMAP.0.borrow_mut();
+    let stale = match cache.as_ref() {
+        Some(map) => map.metric != metric || map.palette[..] != *current,
+        None => true,
+    };
+    if stale {
+        *cache = Some(InverseColormap::build(current.to_vec(), metric));
+    }
+    cache.as_ref().map_or(0, |map| map.lookup(rgb))
+}
+
+// Rec. 601 luma, 0 to 255
+pub fn luminance(rgb: &[u8]) -> f32 {
+    0.299 * rgb[0] as f32 + 0.587 * rgb[1] as f32 + 0.114 * rgb[2] as f32
+}
+
+pub fn palette_color(index: u8) -> [u8; 3] {
+    let palette = PALETTE.0.borrow();
+    let i = index as usize * 3;
+    [palette[i], palette[i + 1], palette[i + 2]]
+}
+
+// Darkest first, equal luminance keeps the given order.
+pub fn sort_by_luminance(indices: &[u8]) -> Vec<u8> {
+    let mut sorted = indices.to_vec();
+    sorted.sort_by(|a, b| {
+        luminance(&palette_color(*a))
+            .partial_cmp(&luminance(&palette_color(*b)))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    sorted
+}
+
+fn lerp_rgb(from: &[u8; 3], to: &[u8; 3], t: f32) -> [u8; 3] {
+    let mut rgb = [0; 3];
+    for (channel, (a, b)) in rgb.iter_mut().zip(from.iter().zip(to.iter())) {
+        *channel = (*a as f32 + (*b as f32 - *a as f32) * t).round() as u8;
+    }
+    rgb
+}
+
+// Overwrites the PALETTE entries between from and to with an even blend of
+// their two colors, the ends are kept.
+pub fn fill_ramp(from: u8, to: u8) {
+    let (from, to) = (from.min(to), from.max(to));
+    let (start, end) = (palette_color(from), palette_color(to));
+    let steps = (to - from) as f32;
+    let mut palette = PALETTE.0.borrow_mut();
+    for index in (from as usize + 1)..(to as usize) {
+        let rgb = lerp_rgb(&start, &end, (index - from as usize) as f32 / steps);
+        palette[(index * 3)..(index * 3 + 3)].copy_from_slice(&rgb);
+    }
+}
+
+// Indices of the existing colors closest to steps points blended between
+// from and to, repeats next to each other are dropped.
+pub fn ramp_indices(from: u8, to: u8, steps: usize, count: usize) -> Vec<u8> {
+    let (start, end) = (palette_color(from), palette_color(to));
+    let labs: Vec<Lab> = {
+        let palette = PALETTE.0.borrow();
+        palette[..(count.min(NUM_COLORS).max(1) * 3)]
+            .chunks(3)
+            .map(Lab::from_rgb)
+            .collect()
+    };
+    let mut ramp: Vec<u8> = vec![];
+    for step in 0..steps.max(2) {
+        let t = step as f32 / (steps.max(2) - 1) as f32;
+        let index = nearest_lab(&labs, &Lab::from_rgb(&lerp_rgb(&start, &end, t))) as u8;
+        if ramp.last() != Some(&index) {
+            ramp.push(index);
+        }
+    }
+    ramp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAYS: [u8; 9] = [0, 0, 0, 128, 128, 128, 255, 255, 255];
+
+    #[test]
+    fn nearest_index_finds_the_closest_color() {
+        for &metric in [ColorMetric::Rgb, ColorMetric::Lab].iter() {
+            assert_eq!(nearest_index(&GRAYS, &[10, 0, 5], metric), 0);
+            assert_eq!(nearest_index(&GRAYS, &[120, 140, 130], metric), 1);
+            assert_eq!(nearest_index(&GRAYS, &[255, 255, 255], metric), 2);
+        }
+    }
+
+    #[test]
+    fn nearest_index_keeps_the_first_of_equal_colors() {
+        let palette = [9, 9, 9, 200, 0, 0, 200, 0, 0];
+        for &metric in [ColorMetric::Rgb, ColorMetric::Lab].iter() {
+            assert_eq!(nearest_index(&palette, &[210, 10, 0], metric), 1);
+        }
+    }
+
+    #[test]
+    fn metrics_can_disagree() {
+        // a dark blue is closer to black in rgb, but looks more like the
+        // bright blue
+        let palette = [0, 0, 0, 0, 0, 255];
+        let dark_blue = [0, 0, 110];
+        assert_eq!(nearest_index(&palette, &dark_blue, ColorMetric::Rgb), 0);
+        assert_eq!(nearest_index(&palette, &dark_blue, ColorMetric::Lab), 1);
+    }
+}
@@ -0,0 +1,292 @@
+use crate::asset::*;
+use crate::color::*;
+use crate::pico::{DEFAULT_COLORS, NUM_COLORS};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ConvertError {
+    Decode(String),
+    TooLarge { width: usize, height: usize },
+    EmptyPalette,
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConvertError::Decode(message) => write!(f, "could not decode png: {}", message),
+            ConvertError::TooLarge { width, height } => write!(
+                f,
+                "{}x{} is larger than the 65535x65535 a .pico can hold",
+                width, height
+            ),
+            ConvertError::EmptyPalette => write!(f, "target palette has no colors"),
+        }
+    }
+}
+
+impl From<png::DecodingError> for ConvertError {
+    fn from(err: png::DecodingError) -> ConvertError {
+        ConvertError::Decode(err.to_string())
+    }
+}
+
+pub enum PaletteSource {
+    // map every pixel to the nearest of these rgb triples
+    Fixed(Vec<u8>),
+    // build a palette of at most this many colors from the image
+    Quantize(usize),
+    // keep the palette of an indexed png, quantize to 256 colors otherwise
+    FromImage,
+}
+
+pub struct ConvertOptions {
+    pub palette: PaletteSource,
+    // pixels with alpha below 128 get this index instead of a color match
+    pub transparent_index: Option<u8>,
+    pub metric: ColorMetric,
+    pub compression: Compression,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> ConvertOptions {
+        ConvertOptions {
+            palette: PaletteSource::FromImage,
+            transparent_index: None,
+            metric: ColorMetric::Lab,
+            compression: Compression::None,
+        }
+    }
+}
+
+pub fn pico8_palette() -> Vec<u8> {
+    DEFAULT_COLORS.to_vec()
+}
+
+// Whole rgb triples only, at most NUM_COLORS of them.
+fn palette_triples(palette: &[u8]) -> Vec<u8> {
+    let len = (palette.len() / 3 * 3).min(NUM_COLORS * 3);
+    palette[..len].to_vec()
+}
+
+pub struct DecodedImage {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+    // palette and one index per pixel when the source was an indexed png
+    pub indexed: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+fn to_rgba(color_type: png::ColorType, data: &[u8]) -> Vec<u8> {
+    match color_type {
+        png::ColorType::RGBA => data.to_vec(),
+        png::ColorType::RGB => data
+            .chunks(3)
+            .flat_map(|p| vec![p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => data
+            .chunks(2)
+            .flat_map(|p| vec![p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => data.iter().flat_map(|g| vec![*g, *g, *g, 255]).collect(),
+        // EXPAND turns indexed images into rgb before we get here
+        png::ColorType::Indexed => data.iter().flat_map(|i| vec![*i, *i, *i, 255]).collect(),
+    }
+}
+
+// Rows of 1, 2 or 4 bit indices are packed high bits first and padded to a
+// whole byte, 8 bit rows are returned as they are.
+fn unpack_indices(packed: &[u8], width: usize, height: usize, bits: usize) -> Vec<u8> {
+    if bits == 8 {
+        return packed[..(width * height)].to_vec();
+    }
+    let stride = (width * bits + 7) / 8;
+    let per_byte = 8 / bits;
+    let mask = (1 << bits) - 1;
+    let mut indices = Vec::with_capacity(width * height);
+    for row in packed.chunks(stride).take(height) {
+        for x in 0..width {
+            let shift = 8 - bits * (x % per_byte + 1);
+            indices.push((row[x / per_byte] >> shift) & mask);
+        }
+    }
+    indices
+}
+
+pub fn decode_png(bytes: &[u8]) -> Result<DecodedImage, ConvertError> {
+    let indexed = {
+        // without this the decoder expands indices to rgb by default
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::IDENTITY);
+        let (info, mut reader) = decoder.read_info()?;
+        if info.color_type == png::ColorType::Indexed {
+            let mut packed = vec![0; info.buffer_size()];
+            reader.next_frame(&mut packed)?;
+            let palette = reader.info().palette.clone().unwrap_or_default();
+            let (width, height) = (info.width as usize, info.height as usize);
+            let indices = unpack_indices(&packed, width, height, info.bit_depth as usize);
+            Some((palette, indices))
+        } else {
+            None
+        }
+    };
+
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let (info, mut reader) = decoder.read_info()?;
+    let mut data = vec![0; info.buffer_size()];
+    reader.next_frame(&mut data)?;
+    Ok(DecodedImage {
+        width: info.width as usize,
+        height: info.height as usize,
+        rgba: to_rgba(info.color_type, &data),
+        indexed,
+    })
+}
+
+// Median cut over the distinct colors of the image, weighted by how often
+// each color appears.
+pub fn quantize(rgba: &[u8], max_colors: usize) -> Vec<u8> {
+    let mut counts: HashMap<[u8; 3], u32> = HashMap::new();
+    for pixel in rgba.chunks(4) {
+        if pixel[3] >= 128 {
+            *counts.entry([pixel[0], pixel[1], pixel[2]]).or_insert(0) += 1;
+        }
+    }
+    let colors: Vec<([u8; 3], u32)> = counts.into_iter().collect();
+    let max_colors = max_colors.max(1).min(NUM_COLORS);
+    if colors.is_empty() {
+        return vec![0, 0, 0];
+    }
+    let mut boxes = vec![colors];
+    while boxes.len() < max_colors {
+        let (index, channel) = match boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let (channel, range) = widest_channel(b);
+                (i, channel, range)
+            })
+            .max_by_key(|(_, _, range)| *range)
+        {
+            Some((i, channel, range)) if range > 0 => (i, channel),
+            _ => break,
+        };
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|(color, _)| color[channel]);
+        let total: u32 = colors.iter().map(|(_, count)| count).sum();
+        let mut seen = 0;
+        let mut split = 1;
+        for (i, (_, count)) in colors.iter().enumerate() {
+            seen += count;
+            if seen * 2 >= total {
+                split = (i + 1).max(1).min(colors.len() - 1);
+                break;
+            }
+        }
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+    let mut palette = vec![];
+    for colors in boxes {
+        let total: u64 = colors.iter().map(|(_, count)| *count as u64).sum();
+        for channel in 0..3 {
+            let sum: u64 = colors
+                .iter()
+                .map(|(color, count)| color[channel] as u64 * *count as u64)
+                .sum();
+            palette.push((sum / total.max(1)) as u8);
+        }
+    }
+    palette
+}
+
+fn widest_channel(colors: &[([u8; 3], u32)]) -> (usize, u8) {
+    let mut best = (0, 0);
+    for channel in 0..3 {
+        let min = colors.iter().map(|(c, _)| c[channel]).min().unwrap_or(0);
+        let max = colors.iter().map(|(c, _)| c[channel]).max().unwrap_or(0);
+        if max - min > best.1 {
+            best = (channel, max - min);
+        }
+    }
+    best
+}
+
+pub fn image_to_asset(
+    image: &DecodedImage,
+    options: &ConvertOptions,
+) -> Result<PicoAsset, ConvertError> {
+    if image.width > 0xFFFF || image.height > 0xFFFF {
+        return Err(ConvertError::TooLarge {
+            width: image.width,
+            height: image.height,
+        });
+    }
+    let palette = match (&options.palette, &image.indexed) {
+        (PaletteSource::FromImage, Some((palette, indices))) => {
+            let palette = palette_triples(palette);
+            if palette.is_empty() {
+                return Err(ConvertError::EmptyPalette);
+            }
+            // the expanded pixels carry the alpha from the png's tRNS chunk
+            let pixels = indices
+                .iter()
+                .zip(image.rgba.chunks(4))
+                .map(|(index, pixel)| match options.transparent_index {
+                    Some(transparent) if pixel[3] < 128 => transparent,
+                    _ => *index,
+                })
+                .collect();
+            return Ok(PicoAsset {
+                width: image.width,
+                height: image.height,
+                palette: Some(palette),
+                pixels,
+                sprite_flags: None,
+                map: None,
+                metadata: vec![],
+            });
+        }
+        (PaletteSource::FromImage, None) => quantize(&image.rgba, NUM_COLORS),
+        (PaletteSource::Quantize(colors), _) => quantize(&image.rgba, *colors),
+        (PaletteSource::Fixed(palette), _) => palette_triples(palette),
+    };
+    if palette.is_empty() {
+        return Err(ConvertError::EmptyPalette);
+    }
+    let labs: Vec<Lab> = palette.chunks(3).map(Lab::from_rgb).collect();
+    let mut cache: HashMap<[u8; 3], u8> = HashMap::new();
+    let pixels = image
+        .rgba
+        .chunks(4)
+        .map(|pixel| match options.transparent_index {
+            Some(index) if pixel[3] < 128 => index,
+            _ => *cache
+                .entry([pixel[0], pixel[1], pixel[2]])
+                .or_insert_with(|| match options.metric {
+                    ColorMetric::Rgb => nearest_index(&palette, pixel, options.metric),
+                    ColorMetric::Lab => nearest_lab(&labs, &Lab::from_rgb(pixel)) as u8,
+                }),
+        })
+        .collect();
+    Ok(PicoAsset {
+        width: image.width,
+        height: image.height,
+        palette: Some(palette),
+        pixels,
+        sprite_flags: None,
+        map: None,
+        metadata: vec![],
+    })
+}
+
+pub fn png_to_pico(bytes: &[u8], options: &ConvertOptions) -> Result<Vec<u8>, ConvertError> {
+    let image = decode_png(bytes)?;
+    Ok(write_pico_with(
+        &image_to_asset(&image, options)?,
+        options.compression,
+    ))
+}
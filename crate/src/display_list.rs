@@ -0,0 +1,724 @@
+use crate::bytes::*;
+use crate::font::*;
+use crate::layout::*;
+use crate::pico::*;
+use std::cmp;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum DrawCommand {
+    Cls {
+        c: i32,
+    },
+    Pset {
+        x: i32,
+        y: i32,
+        c: i32,
+    },
+    Line {
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+        c: i32,
+    },
+    Rect {
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+        c: i32,
+    },
+    RectFill {
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+        c: i32,
+    },
+    Circ {
+        x: i32,
+        y: i32,
+        r: i32,
+        c: i32,
+    },
+    CircFill {
+        x: i32,
+        y: i32,
+        r: i32,
+        c: i32,
+    },
+    Tri {
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        c: i32,
+    },
+    TriFill {
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        c: i32,
+    },
+    Prnt {
+        string: String,
+        font: FontHandle,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        c: i32,
+    },
+    CopySprite {
+        source: u8,
+        source_x: i32,
+        source_y: i32,
+        target_x: i32,
+        target_y: i32,
+        width: usize,
+        height: usize,
+        transparency: bool,
+    },
+}
+
+impl DrawCommand {
+    fn tag(&self) -> u8 {
+        match self {
+            DrawCommand::Cls { .. } => 0,
+            DrawCommand::Pset { .. } => 1,
+            DrawCommand::Line { .. } => 2,
+            DrawCommand::Rect { .. } => 3,
+            DrawCommand::RectFill { .. } => 4,
+            DrawCommand::Circ { .. } => 5,
+            DrawCommand::CircFill { .. } => 6,
+            DrawCommand::Tri { .. } => 7,
+            DrawCommand::TriFill { .. } => 8,
+            DrawCommand::Prnt { .. } => 9,
+            DrawCommand::CopySprite { .. } => 10,
+        }
+    }
+
+    pub fn draw(&self) {
+        match *self {
+            DrawCommand::Cls { c } => cls(c),
+            DrawCommand::Pset { x, y, c } => pset(x, y, c),
+            DrawCommand::Line { x0, y0, x1, y1, c } => line(x0, y0, x1, y1, c),
+            DrawCommand::Rect { x0, y0, x1, y1, c } => rect(x0, y0, x1, y1, c),
+            DrawCommand::RectFill { x0, y0, x1, y1, c } => rect_fill(x0, y0, x1, y1, c),
+            DrawCommand::Circ { x, y, r, c } => circ(x, y, r, c),
+            DrawCommand::CircFill { x, y, r, c } => circ_fill(x, y, r, c),
+            DrawCommand::Tri {
+                x0,
+                y0,
+                x1,
+                y1,
+                x2,
+                y2,
+                c,
+            } => tri(x0, y0, x1, y1, x2, y2, c),
+            DrawCommand::TriFill {
+                x0,
+                y0,
+                x1,
+                y1,
+                x2,
+                y2,
+                c,
+            } => tri_fill(x0, y0, x1, y1, x2, y2, c),
+            DrawCommand::Prnt {
+                ref string,
+                font,
+                x,
+                y,
+                w,
+                h,
+                c,
+            } => {
+                prnt(string, font, x, y, w, h, c);
+            }
+            DrawCommand::CopySprite {
+                source,
+                source_x,
+                source_y,
+                target_x,
+                target_y,
+                width,
+                height,
+                transparency,
+            } => {
+                let target = get_target();
+                if transparency {
+                    copy_sprite_with_transparency(
+                        source, target, source_x, source_y, target_x, target_y, width, height,
+                    );
+                } else {
+                    copy_sprite(
+                        source, target, source_x, source_y, target_x, target_y, width, height,
+                    );
+                }
+            }
+        }
+    }
+
+    // Conservative bounding box of the pixels this command can touch, in the
+    // coordinates it was recorded with. `None` means the whole target.
+    pub fn bounds(&self) -> Option<ClipRect> {
+        match *self {
+            DrawCommand::Cls { .. } => None,
+            DrawCommand::Pset { x, y, .. } => Some(ClipRect {
+                l: x,
+                t: y,
+                r: x + 1,
+                b: y + 1,
+            }),
+            DrawCommand::Line { x0, y0, x1, y1, .. }
+            | DrawCommand::Rect { x0, y0, x1, y1, .. }
+            | DrawCommand::RectFill { x0, y0, x1, y1, .. } => {
+                let (l, t, r, b) = rect_swap(x0, y0, x1, y1);
+                Some(ClipRect {
+                    l,
+                    t,
+                    r: r + 1,
+                    b: b + 1,
+                })
+            }
+            DrawCommand::Circ { x, y, r, .. } | DrawCommand::CircFill { x, y, r, .. } => {
+                let r = cmp::max(r, 0);
+                Some(ClipRect {
+                    l: x - r,
+                    t: y - r,
+                    r: x + r + 1,
+                    b: y + r + 1,
+                })
+            }
+            DrawCommand::Tri {
+                x0,
+                y0,
+                x1,
+                y1,
+                x2,
+                y2,
+                ..
+            }
+            | DrawCommand::TriFill {
+                x0,
+                y0,
+                x1,
+                y1,
+                x2,
+                y2,
+                ..
+            } => Some(ClipRect {
+                l: cmp::min(x0, cmp::min(x1, x2)),
+                t: cmp::min(y0, cmp::min(y1, y2)),
+                // tri_fill spans reach one pixel past the right edge
+                r: cmp::max(x0, cmp::max(x1, x2)) + 2,
+                b: cmp::max(y0, cmp::max(y1, y2)) + 1,
+            }),
+            DrawCommand::Prnt {
+                ref string,
+                font,
+                x,
+                y,
+                w,
+                h,
+                ..
+            } => {
+                let (width, height) = text_size(string, font);
+                // glyph offsets and kerning can reach past the advances
+                let slack = with_font(font, |font| font.line_height);
+                Some(ClipRect {
+                    l: x - slack,
+                    t: y - slack,
+                    r: x + width + slack + cmp::max(w, 1),
+                    b: y + height + slack + cmp::max(h, 1),
+                })
+            }
+            DrawCommand::CopySprite {
+                target_x,
+                target_y,
+                width,
+                height,
+                ..
+            } => Some(ClipRect {
+                l: target_x,
+                t: target_y,
+                r: target_x + width as i32,
+                b: target_y + height as i32,
+            }),
+        }
+    }
+}
+
+fn union_rect(a: Option<ClipRect>, b: Option<ClipRect>) -> Option<ClipRect> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(ClipRect {
+            l: cmp::min(a.l, b.l),
+            t: cmp::min(a.t, b.t),
+            r: cmp::max(a.r, b.r),
+            b: cmp::max(a.b, b.b),
+        }),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub enum DisplayListDiff {
+    Unchanged,
+    Region(ClipRect),
+    Everything,
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct DisplayList {
+    pub commands: Vec<DrawCommand>,
+}
+
+impl DisplayList {
+    pub fn new() -> DisplayList {
+        DisplayList { commands: vec![] }
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn push(&mut self, command: DrawCommand) {
+        self.commands.push(command);
+    }
+
+    pub fn cls(&mut self, c: i32) {
+        self.push(DrawCommand::Cls { c });
+    }
+
+    pub fn pset(&mut self, x: i32, y: i32, c: i32) {
+        self.push(DrawCommand::Pset { x, y, c });
+    }
+
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, c: i32) {
+        self.push(DrawCommand::Line { x0, y0, x1, y1, c });
+    }
+
+    pub fn rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, c: i32) {
+        self.push(DrawCommand::Rect { x0, y0, x1, y1, c });
+    }
+
+    pub fn rect_fill(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, c: i32) {
+        self.push(DrawCommand::RectFill { x0, y0, x1, y1, c });
+    }
+
+    pub fn circ(&mut self, x: i32, y: i32, r: i32, c: i32) {
+        self.push(DrawCommand::Circ { x, y, r, c });
+    }
+
+    pub fn circ_fill(&mut self, x: i32, y: i32, r: i32, c: i32) {
+        self.push(DrawCommand::CircFill { x, y, r, c });
+    }
+
+    pub fn tri(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, x2: i32, y2: i32, c: i32) {
+        self.push(DrawCommand::Tri {
+            x0,
+            y0,
+            x1,
+            y1,
+            x2,
+            y2,
+            c,
+        });
+    }
+
+    pub fn tri_fill(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, x2: i32, y2: i32, c: i32) {
+        self.push(DrawCommand::TriFill {
+            x0,
+            y0,
+            x1,
+            y1,
+            x2,
+            y2,
+            c,
+        });
+    }
+
+    pub fn prnt(
+        &mut self,
+        string: &str,
+        font: FontHandle,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        c: i32,
+    ) {
+        self.push(DrawCommand::Prnt {
+            string: string.to_owned(),
+            font,
+            x,
+            y,
+            w,
+            h,
+            c,
+        });
+    }
+
+    pub fn copy_sprite(
+        &mut self,
+        source: u8,
+        source_x: i32,
+        source_y: i32,
+        target_x: i32,
+        target_y: i32,
+        width: usize,
+        height: usize,
+    ) {
+        self.push(DrawCommand::CopySprite {
+            source,
+            source_x,
+            source_y,
+            target_x,
+            target_y,
+            width,
+            height,
+            transparency: false,
+        });
+    }
+
+    pub fn copy_sprite_with_transparency(
+        &mut self,
+        source: u8,
+        source_x: i32,
+        source_y: i32,
+        target_x: i32,
+        target_y: i32,
+        width: usize,
+        height: usize,
+    ) {
+        self.push(DrawCommand::CopySprite {
+            source,
+            source_x,
+            source_y,
+            target_x,
+            target_y,
+            width,
+            height,
+            transparency: true,
+        });
+    }
+
+    pub fn replay(&self) {
+        for command in &self.commands {
+            command.draw();
+        }
+    }
+
+    pub fn replay_into(&self, target: u8) {
+        let old_target = get_target();
+        set_target(target);
+        self.replay();
+        set_target(old_target);
+    }
+
+    pub fn bounds(&self) -> Option<ClipRect> {
+        let mut iter = self.commands.iter();
+        let first = iter.next()?.bounds();
+        iter.fold(first, |acc, command| union_rect(acc, command.bounds()))
+    }
+
+    // Compares against the list drawn last frame. Commands are matched by
+    // position, so everything from the first mismatch onwards counts as
+    // changed on both sides.
+    pub fn diff(&self, previous: &DisplayList) -> DisplayListDiff {
+        let first_change = self
+            .commands
+            .iter()
+            .zip(previous.commands.iter())
+            .position(|(a, b)| a != b)
+            .unwrap_or_else(|| cmp::min(self.len(), previous.len()));
+        if first_change == self.len() && first_change == previous.len() {
+            return DisplayListDiff::Unchanged;
+        }
+        let changed = self.commands[first_change..]
+            .iter()
+            .chain(previous.commands[first_change..].iter());
+        let mut region: Option<ClipRect> = None;
+        for command in changed {
+            match command.bounds() {
+                Some(bounds) => {
+                    region = match region {
+                        Some(region) => union_rect(Some(region), Some(bounds)),
+                        None => Some(bounds),
+                    };
+                }
+                None => return DisplayListDiff::Everything,
+            }
+        }
+        match region {
+            Some(region) => DisplayListDiff::Region(region),
+            None => DisplayListDiff::Unchanged,
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(DISPLAY_LIST_MAGIC);
+        push_u32_le(&mut bytes, self.commands.len() as u32);
+        for command in &self.commands {
+            bytes.push(command.tag());
+            match *command {
+                DrawCommand::Cls { c } => push_i32s(&mut bytes, &[c]),
+                DrawCommand::Pset { x, y, c } => push_i32s(&mut bytes, &[x, y, c]),
+                DrawCommand::Line { x0, y0, x1, y1, c }
+                | DrawCommand::Rect { x0, y0, x1, y1, c }
+                | DrawCommand::RectFill { x0, y0, x1, y1, c } => {
+                    push_i32s(&mut bytes, &[x0, y0, x1, y1, c])
+                }
+                DrawCommand::Circ { x, y, r, c } | DrawCommand::CircFill { x, y, r, c } => {
+                    push_i32s(&mut bytes, &[x, y, r, c])
+                }
+                DrawCommand::Tri {
+                    x0,
+                    y0,
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    c,
+                }
+                | DrawCommand::TriFill {
+                    x0,
+                    y0,
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    c,
+                } => push_i32s(&mut bytes, &[x0, y0, x1, y1, x2, y2, c]),
+                DrawCommand::Prnt {
+                    ref string,
+                    font,
+                    x,
+                    y,
+                    w,
+                    h,
+                    c,
+                } => {
+                    push_u32_le(&mut bytes, string.len() as u32);
+                    bytes.extend_from_slice(string.as_bytes());
+                    push_u32_le(&mut bytes, font as u32);
+                    push_i32s(&mut bytes, &[x, y, w, h, c]);
+                }
+                DrawCommand::CopySprite {
+                    source,
+                    source_x,
+                    source_y,
+                    target_x,
+                    target_y,
+                    width,
+                    height,
+                    transparency,
+                } => {
+                    bytes.push(source);
+                    bytes.push(transparency as u8);
+                    push_i32s(
+                        &mut bytes,
+                        &[
+                            source_x,
+                            source_y,
+                            target_x,
+                            target_y,
+                            width as i32,
+                            height as i32,
+                        ],
+                    );
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<DisplayList, ReadError> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(DISPLAY_LIST_MAGIC.len())? != DISPLAY_LIST_MAGIC {
+            return Err(ReadError::BadMagic);
+        }
+        let count = reader.u32()? as usize;
+        let mut list = DisplayList::new();
+        for _ in 0..count {
+            let command = match reader.u8()? {
+                0 => DrawCommand::Cls { c: reader.i32()? },
+                1 => DrawCommand::Pset {
+                    x: reader.i32()?,
+                    y: reader.i32()?,
+                    c: reader.i32()?,
+                },
+                tag @ 2..=4 => {
+                    let (x0, y0, x1, y1, c) = (
+                        reader.i32()?,
+                        reader.i32()?,
+                        reader.i32()?,
+                        reader.i32()?,
+                        reader.i32()?,
+                    );
+                    match tag {
+                        2 => DrawCommand::Line { x0, y0, x1, y1, c },
+                        3 => DrawCommand::Rect { x0, y0, x1, y1, c },
+                        _ => DrawCommand::RectFill { x0, y0, x1, y1, c },
+                    }
+                }
+                tag @ 5..=6 => {
+                    let (x, y, r, c) = (reader.i32()?, reader.i32()?, reader.i32()?, reader.i32()?);
+                    if tag == 5 {
+                        DrawCommand::Circ { x, y, r, c }
+                    } else {
+                        DrawCommand::CircFill { x, y, r, c }
+                    }
+                }
+                tag @ 7..=8 => {
+                    let (x0, y0, x1, y1, x2, y2, c) = (
+                        reader.i32()?,
+                        reader.i32()?,
+                        reader.i32()?,
+                        reader.i32()?,
+                        reader.i32()?,
+                        reader.i32()?,
+                        reader.i32()?,
+                    );
+                    if tag == 7 {
+                        DrawCommand::Tri {
+                            x0,
+                            y0,
+                            x1,
+                            y1,
+                            x2,
+                            y2,
+                            c,
+                        }
+                    } else {
+                        DrawCommand::TriFill {
+                            x0,
+                            y0,
+                            x1,
+                            y1,
+                            x2,
+                            y2,
+                            c,
+                        }
+                    }
+                }
+                9 => {
+                    let len = reader.u32()? as usize;
+                    let string = String::from_utf8(reader.take(len)?.to_vec())
+                        .map_err(|_| ReadError::Invalid("prnt string is not utf-8"))?;
+                    DrawCommand::Prnt {
+                        string,
+                        font: reader.u32()? as FontHandle,
+                        x: reader.i32()?,
+                        y: reader.i32()?,
+                        w: reader.i32()?,
+                        h: reader.i32()?,
+                        c: reader.i32()?,
+                    }
+                }
+                10 => DrawCommand::CopySprite {
+                    source: reader.u8()?,
+                    transparency: reader.u8()? != 0,
+                    source_x: reader.i32()?,
+                    source_y: reader.i32()?,
+                    target_x: reader.i32()?,
+                    target_y: reader.i32()?,
+                    width: reader.i32()? as usize,
+                    height: reader.i32()? as usize,
+                },
+                _ => return Err(ReadError::Invalid("unknown draw command")),
+            };
+            list.push(command);
+        }
+        Ok(list)
+    }
+}
+
+const DISPLAY_LIST_MAGIC: &[u8] = b"PDL1";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> DisplayList {
+        let mut list = DisplayList::new();
+        list.cls(1);
+        list.rect_fill(1, 2, 30, 40, 9);
+        list.circ(10, 10, 5, 7);
+        list.tri_fill(0, 0, 8, 0, 4, 6, 3);
+        list.prnt("hi", DEFAULT_FONT, 2, 3, 1, 1, 7);
+        list
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let list = sample();
+        assert_eq!(DisplayList::deserialize(&list.serialize()), Ok(list));
+    }
+
+    #[test]
+    fn truncated_bytes_are_an_error() {
+        let bytes = sample().serialize();
+        for len in 0..bytes.len() {
+            assert!(DisplayList::deserialize(&bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn huge_string_length_is_an_error() {
+        let mut list = DisplayList::new();
+        list.prnt("hi", DEFAULT_FONT, 2, 3, 1, 1, 7);
+        let mut bytes = list.serialize();
+        // the string length follows the magic, the count and the tag
+        let offset = DISPLAY_LIST_MAGIC.len() + 4 + 1;
+        bytes[offset..(offset + 4)].copy_from_slice(&std::u32::MAX.to_le_bytes());
+        assert_eq!(
+            DisplayList::deserialize(&bytes),
+            Err(ReadError::UnexpectedEnd {
+                offset: offset + 4,
+                len: std::u32::MAX as usize,
+            })
+        );
+    }
+
+    #[test]
+    fn diff_finds_the_changed_region() {
+        let mut a = DisplayList::new();
+        a.rect_fill(0, 0, 10, 10, 1);
+        a.rect_fill(20, 20, 30, 30, 2);
+        let mut b = a.clone();
+        match a.diff(&b) {
+            DisplayListDiff::Unchanged => {}
+            diff => panic!("{:?}", diff),
+        }
+        b.commands[1] = DrawCommand::RectFill {
+            x0: 40,
+            y0: 40,
+            x1: 50,
+            y1: 50,
+            c: 2,
+        };
+        match b.diff(&a) {
+            DisplayListDiff::Region(region) => {
+                assert_eq!((region.l, region.t), (20, 20));
+                assert!(region.r >= 50 && region.b >= 50);
+            }
+            diff => panic!("{:?}", diff),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use crate::asset::{blit_pixels, AssetError};
+use crate::bytes::*;
+use crate::pico::*;
+use euclid::Point2D;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Stroke {
+    pub points: Vec<Point2D<f64>>,
+    pub color: i32,
+    // full width in pixels, fat_line_strip takes half of it
+    pub width: f64,
+}
+
+impl Stroke {
+    pub fn draw(&self) {
+        fat_line_strip(&self.points, self.width / 2.0, self.color);
+    }
+}
+
+// What a sketch needs to put its canvas back: vector strokes that are
+// replayed in order, on top of an optional width x height raster layer.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Document {
+    pub width: usize,
+    pub height: usize,
+    pub strokes: Vec<Stroke>,
+    pub raster: Option<Vec<u8>>,
+}
+
+fn svg_color(c: i32) -> String {
+    let swapped = PALETTE_SWAP.0.borrow()[(c as usize) % NUM_COLORS] as usize * 3;
+    let palette = PALETTE.0.borrow();
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        palette[swapped],
+        palette[swapped + 1],
+        palette[swapped + 2]
+    )
+}
+
+const DOCUMENT_MAGIC: &[u8] = b"PDC1";
+
+fn push_f64(bytes: &mut Vec<u8>, v: f64) {
+    let bits = v.to_bits();
+    push_u32_le(bytes, bits as u32);
+    push_u32_le(bytes, (bits >> 32) as u32);
+}
+
+fn read_f64(reader: &mut Reader) -> Result<f64, ReadError> {
+    let low = reader.u32()? as u64;
+    let high = reader.u32()? as u64;
+    Ok(f64::from_bits(low | (high << 32)))
+}
+
+impl Document {
+    pub fn new(width: usize, height: usize) -> Document {
+        Document {
+            width,
+            height,
+            strokes: vec![],
+            raster: None,
+        }
+    }
+
+    // A document of the current screen size holding target's pixels.
+    pub fn from_target(target: u8) -> Document {
+        let (width, height) = (WIDTH(), HEIGHT());
+        let mut document = Document::new(width, height);
+        document.raster = Some(screen(target)[..(width * height)].to_vec());
+        document
+    }
+
+    // Clears target, then draws the raster and every stroke into it. Fails
+    // when the raster is bigger than the screen.
+    pub fn draw(&self, target: u8) -> Result<(), AssetError> {
+        set_target(target);
+        cls(0);
+        if let Some(raster) = &self.raster {
+            blit_pixels(target, self.width, self.height, raster)?;
+        }
+        for stroke in &self.strokes {
+            stroke.draw();
+        }
+        Ok(())
+    }
+
+    // The strokes as an svg, colors resolved through PALETTE_SWAP and
+    // PALETTE the way they are on screen. The raster layer is left out.
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            w = self.width,
+            h = self.height
+        );
+        svg.push_str(&format!(
+            "  <rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            self.width,
+            self.height,
+            svg_color(0)
+        ));
+        for stroke in &self.strokes {
+            let color = svg_color(stroke.color);
+            match stroke.points.len() {
+                0 => {}
+                1 => svg.push_str(&format!(
+                    "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+                    stroke.points[0].x,
+                    stroke.points[0].y,
+                    stroke.width / 2.0,
+                    color
+                )),
+                _ => {
+                    let points: Vec<String> = stroke
+                        .points
+                        .iter()
+                        .map(|p| format!("{},{}", p.x, p.y))
+                        .collect();
+                    svg.push_str(&format!(
+                        "  <polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-linejoin=\"round\" stroke-linecap=\"round\"/>\n",
+                        points.join(" "),
+                        color,
+                        stroke.width
+                    ));
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(DOCUMENT_MAGIC);
+        push_u32_le(&mut bytes, self.width as u32);
+        push_u32_le(&mut bytes, self.height as u32);
+        push_u32_le(&mut bytes, self.strokes.len() as u32);
+        for stroke in &self.strokes {
+            push_i32s(&mut bytes, &[stroke.color]);
+            push_f64(&mut bytes, stroke.width);
+            push_u32_le(&mut bytes, stroke.points.len() as u32);
+            for point in &stroke.points {
+                push_f64(&mut bytes, point.x);
+                push_f64(&mut bytes, point.y);
+            }
+        }
+        match &self.raster {
+            Some(raster) => {
+                bytes.push(1);
+                bytes.extend_from_slice(raster);
+            }
+            None => bytes.push(0),
+        }
+        bytes
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Document, ReadError> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(DOCUMENT_MAGIC.len())? != DOCUMENT_MAGIC {
+            return Err(ReadError::BadMagic);
+        }
+        let width = reader.u32()? as usize;
+        let height = reader.u32()? as usize;
+        if width > MAX_WIDTH || height > MAX_HEIGHT {
+            return Err(ReadError::Invalid("document is larger than the screen can be"));
+        }
+        let count = reader.u32()? as usize;
+        let mut strokes = vec![];
+        for _ in 0..count {
+            let color = reader.i32()?;
+            let width = read_f64(&mut reader)?;
+            let len = reader.u32()? as usize;
+            let mut points = vec![];
+            for _ in 0..len {
+                let x = read_f64(&mut reader)?;
+                let y = read_f64(&mut reader)?;
+                points.push(Point2D::new(x, y));
+            }
+            strokes.push(Stroke {
+                points,
+                color,
+                width,
+            });
+        }
+        let raster = match reader.u8()? {
+            0 => None,
+            1 => Some(reader.take(width * height)?.to_vec()),
+            _ => return Err(ReadError::Invalid("unknown raster flag")),
+        };
+        Ok(Document {
+            width,
+            height,
+            strokes,
+            raster,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_strokes_and_raster() {
+        let mut document = Document::new(3, 2);
+        document.strokes.push(Stroke {
+            points: vec![Point2D::new(0.5, 1.25), Point2D::new(-3.0, 1e10)],
+            color: 7,
+            width: 6.0,
+        });
+        document.raster = Some(vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(Document::deserialize(&document.serialize()), Ok(document));
+    }
+
+    #[test]
+    fn rejects_truncated_and_foreign_bytes() {
+        let bytes = Document::new(1, 1).serialize();
+        assert!(Document::deserialize(&bytes[..(bytes.len() - 1)]).is_err());
+        assert_eq!(Document::deserialize(b"PDL1"), Err(ReadError::BadMagic));
+    }
+}
@@ -1,3 +1,6 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
 pub const BYTES_PER_CHAR: usize = 8;
 pub const BIT_FONT: [u8; 94 * BYTES_PER_CHAR] = [
     0x18, 0x18, 0x18, 0x18, 0x00, 0x00, 0x18, 0x00, // "!"
@@ -95,3 +98,531 @@ pub const BIT_FONT: [u8; 94 * BYTES_PER_CHAR] = [
     0x0C, 0x18, 0x18, 0x70, 0x18, 0x18, 0x0C, 0x00, // "}"
     0x00, 0x00, 0x00, 0x9C, 0x72, 0x00, 0x00, 0x00, // "~"
 ];
+
+pub const PICO8_BYTES_PER_CHAR: usize = 5;
+pub const PICO8_FONT: [u8; 94 * PICO8_BYTES_PER_CHAR] = [
+    0x02, 0x02, 0x02, 0x00, 0x02, // "!"
+    0x05, 0x05, 0x00, 0x00, 0x00, // """
+    0x05, 0x07, 0x05, 0x07, 0x05, // "#"
+    0x07, 0x03, 0x07, 0x06, 0x07, // "$"
+    0x05, 0x04, 0x02, 0x01, 0x05, // "%"
+    0x03, 0x03, 0x07, 0x05, 0x07, // "&"
+    0x02, 0x01, 0x00, 0x00, 0x00, // "'"
+    0x02, 0x01, 0x01, 0x01, 0x02, // "("
+    0x02, 0x04, 0x04, 0x04, 0x02, // ")"
+    0x05, 0x02, 0x07, 0x02, 0x05, // "*"
+    0x00, 0x02, 0x07, 0x02, 0x00, // "+"
+    0x00, 0x00, 0x00, 0x02, 0x01, // ","
+    0x00, 0x00, 0x07, 0x00, 0x00, // "-"
+    0x00, 0x00, 0x00, 0x00, 0x02, // "."
+    0x04, 0x02, 0x02, 0x02, 0x01, // "/"
+    0x07, 0x05, 0x05, 0x05, 0x07, // "0"
+    0x03, 0x02, 0x02, 0x02, 0x07, // "1"
+    0x07, 0x04, 0x07, 0x01, 0x07, // "2"
+    0x07, 0x04, 0x06, 0x04, 0x07, // "3"
+    0x05, 0x05, 0x07, 0x04, 0x04, // "4"
+    0x07, 0x01, 0x07, 0x04, 0x07, // "5"
+    0x01, 0x01, 0x07, 0x05, 0x07, // "6"
+    0x07, 0x04, 0x04, 0x04, 0x04, // "7"
+    0x07, 0x05, 0x07, 0x05, 0x07, // "8"
+    0x07, 0x05, 0x07, 0x04, 0x04, // "9"
+    0x00, 0x02, 0x00, 0x02, 0x00, // ":"
+    0x00, 0x02, 0x00, 0x02, 0x01, // ";"
+    0x04, 0x02, 0x01, 0x02, 0x04, // "<"
+    0x00, 0x07, 0x00, 0x07, 0x00, // "="
+    0x01, 0x02, 0x04, 0x02, 0x01, // ">"
+    0x07, 0x04, 0x06, 0x00, 0x02, // "?"
+    0x02, 0x05, 0x05, 0x01, 0x06, // "@"
+    0x07, 0x05, 0x07, 0x05, 0x05, // "A"
+    0x07, 0x05, 0x03, 0x05, 0x07, // "B"
+    0x06, 0x01, 0x01, 0x01, 0x06, // "C"
+    0x03, 0x05, 0x05, 0x05, 0x07, // "D"
+    0x07, 0x01, 0x03, 0x01, 0x07, // "E"
+    0x07, 0x01, 0x03, 0x01, 0x01, // "F"
+    0x06, 0x01, 0x01, 0x05, 0x07, // "G"
+    0x05, 0x05, 0x07, 0x05, 0x05, // "H"
+    0x07, 0x02, 0x02, 0x02, 0x07, // "I"
+    0x07, 0x02, 0x02, 0x02, 0x03, // "J"
+    0x05, 0x05, 0x03, 0x05, 0x05, // "K"
+    0x01, 0x01, 0x01, 0x01, 0x07, // "L"
+    0x07, 0x07, 0x05, 0x05, 0x05, // "M"
+    0x03, 0x05, 0x05, 0x05, 0x05, // "N"
+    0x06, 0x05, 0x05, 0x05, 0x03, // "O"
+    0x07, 0x05, 0x07, 0x01, 0x01, // "P"
+    0x02, 0x05, 0x05, 0x03, 0x06, // "Q"
+    0x07, 0x05, 0x03, 0x05, 0x05, // "R"
+    0x06, 0x01, 0x07, 0x04, 0x03, // "S"
+    0x07, 0x02, 0x02, 0x02, 0x02, // "T"
+    0x05, 0x05, 0x05, 0x05, 0x06, // "U"
+    0x05, 0x05, 0x05, 0x02, 0x02, // "V"
+    0x05, 0x05, 0x05, 0x07, 0x07, // "W"
+    0x05, 0x05, 0x02, 0x05, 0x05, // "X"
+    0x05, 0x05, 0x07, 0x04, 0x07, // "Y"
+    0x07, 0x04, 0x02, 0x01, 0x07, // "Z"
+    0x03, 0x01, 0x01, 0x01, 0x03, // "["
+    0x01, 0x02, 0x02, 0x02, 0x04, // "\"
+    0x06, 0x04, 0x04, 0x04, 0x06, // "]"
+    0x02, 0x05, 0x00, 0x00, 0x00, // "^"
+    0x00, 0x00, 0x00, 0x00, 0x07, // "_"
+    0x02, 0x04, 0x00, 0x00, 0x00, // "`"
+    0x07, 0x05, 0x07, 0x05, 0x05, // "a"
+    0x07, 0x05, 0x03, 0x05, 0x07, // "b"
+    0x06, 0x01, 0x01, 0x01, 0x06, // "c"
+    0x03, 0x05, 0x05, 0x05, 0x07, // "d"
+    0x07, 0x01, 0x03, 0x01, 0x07, // "e"
+    0x07, 0x01, 0x03, 0x01, 0x01, // "f"
+    0x06, 0x01, 0x01, 0x05, 0x07, // "g"
+    0x05, 0x05, 0x07, 0x05, 0x05, // "h"
+    0x07, 0x02, 0x02, 0x02, 0x07, // "i"
+    0x07, 0x02, 0x02, 0x02, 0x03, // "j"
+    0x05, 0x05, 0x03, 0x05, 0x05, // "k"
+    0x01, 0x01, 0x01, 0x01, 0x07, // "l"
+    0x07, 0x07, 0x05, 0x05, 0x05, // "m"
+    0x03, 0x05, 0x05, 0x05, 0x05, // "n"
+    0x06, 0x05, 0x05, 0x05, 0x03, // "o"
+    0x07, 0x05, 0x07, 0x01, 0x01, // "p"
+    0x02, 0x05, 0x05, 0x03, 0x06, // "q"
+    0x07, 0x05, 0x03, 0x05, 0x05, // "r"
+    0x06, 0x01, 0x07, 0x04, 0x03, // "s"
+    0x07, 0x02, 0x02, 0x02, 0x02, // "t"
+    0x05, 0x05, 0x05, 0x05, 0x06, // "u"
+    0x05, 0x05, 0x05, 0x02, 0x02, // "v"
+    0x05, 0x05, 0x05, 0x07, 0x07, // "w"
+    0x05, 0x05, 0x02, 0x05, 0x05, // "x"
+    0x05, 0x05, 0x07, 0x04, 0x07, // "y"
+    0x07, 0x04, 0x02, 0x01, 0x07, // "z"
+    0x06, 0x02, 0x03, 0x02, 0x06, // "{"
+    0x02, 0x02, 0x02, 0x02, 0x02, // "|"
+    0x03, 0x02, 0x06, 0x02, 0x03, // "}"
+    0x00, 0x04, 0x07, 0x01, 0x00, // "~"
+
+];
+
+pub const EXTENDED_GLYPHS: [(char, [u8; 8]); 51] = [
+    ('\u{A1}', [0x00, 0x18, 0x00, 0x18, 0x18, 0x18, 0x18, 0x00]), // "¡"
+    ('\u{BF}', [0x18, 0x00, 0x18, 0x30, 0xC0, 0x66, 0x3C, 0x00]), // "¿"
+    ('\u{A2}', [0x18, 0x7C, 0x06, 0x06, 0x06, 0x7C, 0x18, 0x00]), // "¢"
+    ('\u{A3}', [0x38, 0x6C, 0x0C, 0x3E, 0x0C, 0x0C, 0x7E, 0x00]), // "£"
+    ('\u{A5}', [0x66, 0x66, 0x3C, 0x7E, 0x18, 0x7E, 0x18, 0x00]), // "¥"
+    ('\u{A9}', [0x3C, 0x42, 0x99, 0x85, 0x85, 0x99, 0x42, 0x3C]), // "©"
+    ('\u{B0}', [0x1C, 0x36, 0x1C, 0x00, 0x00, 0x00, 0x00, 0x00]), // "°"
+    ('\u{B7}', [0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00]), // "·"
+    ('\u{AB}', [0x00, 0xCC, 0x66, 0x33, 0x66, 0xCC, 0x00, 0x00]), // "«"
+    ('\u{BB}', [0x00, 0x33, 0x66, 0xCC, 0x66, 0x33, 0x00, 0x00]), // "»"
+    ('\u{D7}', [0x00, 0x66, 0x3C, 0x18, 0x3C, 0x66, 0x00, 0x00]), // "×"
+    ('\u{F7}', [0x00, 0x18, 0x00, 0x7E, 0x00, 0x18, 0x00, 0x00]), // "÷"
+    ('\u{DF}', [0x3C, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3E, 0x06]), // "ß"
+    ('\u{C6}', [0xFC, 0x36, 0x33, 0x7F, 0x33, 0x33, 0xF3, 0x00]), // "Æ"
+    ('\u{E6}', [0x00, 0x00, 0x6E, 0xB0, 0x7E, 0x33, 0x6E, 0x00]), // "æ"
+    ('\u{D8}', [0xBC, 0x66, 0x76, 0x7E, 0x6E, 0x66, 0x3D, 0x00]), // "Ø"
+    ('\u{F8}', [0x00, 0x00, 0xBC, 0x76, 0x7E, 0x6E, 0x3D, 0x00]), // "ø"
+    ('\u{2190}', [0x00, 0x08, 0x0C, 0xFE, 0x0C, 0x08, 0x00, 0x00]), // "←"
+    ('\u{2191}', [0x18, 0x3C, 0x7E, 0x18, 0x18, 0x18, 0x18, 0x00]), // "↑"
+    ('\u{2192}', [0x00, 0x10, 0x30, 0x7F, 0x30, 0x10, 0x00, 0x00]), // "→"
+    ('\u{2193}', [0x18, 0x18, 0x18, 0x18, 0x7E, 0x3C, 0x18, 0x00]), // "↓"
+    ('\u{2194}', [0x00, 0x24, 0x66, 0xFF, 0x66, 0x24, 0x00, 0x00]), // "↔"
+    ('\u{2195}', [0x18, 0x3C, 0x7E, 0x18, 0x7E, 0x3C, 0x18, 0x00]), // "↕"
+    ('\u{25CF}', [0x3C, 0x7E, 0xFF, 0xFF, 0xFF, 0x7E, 0x3C, 0x00]), // "●"
+    ('\u{2665}', [0x36, 0x7F, 0x7F, 0x7F, 0x3E, 0x1C, 0x08, 0x00]), // "♥"
+    ('\u{2609}', [0x3C, 0x42, 0x99, 0xBD, 0x99, 0x42, 0x3C, 0x00]), // "☉"
+    ('\u{C6C3}', [0x1C, 0x1C, 0x08, 0x7F, 0x1C, 0x36, 0x22, 0x00]), // "웃"
+    ('\u{2302}', [0x08, 0x1C, 0x3E, 0x7F, 0x36, 0x36, 0x3E, 0x00]), // "⌂"
+    ('\u{266A}', [0x78, 0x48, 0x48, 0x48, 0x6E, 0xEF, 0x46, 0x00]), // "♪"
+    ('\u{25C6}', [0x08, 0x1C, 0x3E, 0x7F, 0x3E, 0x1C, 0x08, 0x00]), // "◆"
+    ('\u{2026}', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xDB, 0x00]), // "…"
+    ('\u{2605}', [0x08, 0x08, 0x7F, 0x3E, 0x1C, 0x36, 0x22, 0x00]), // "★"
+    ('\u{29D7}', [0x7F, 0x3E, 0x1C, 0x08, 0x1C, 0x3E, 0x7F, 0x00]), // "⧗"
+    ('\u{2C7}', [0x22, 0x14, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00]), // "ˇ"
+    ('\u{2227}', [0x08, 0x1C, 0x36, 0x63, 0x41, 0x00, 0x00, 0x00]), // "∧"
+    ('\u{25A4}', [0x7F, 0x00, 0x7F, 0x00, 0x7F, 0x00, 0x7F, 0x00]), // "▤"
+    ('\u{25A5}', [0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x00]), // "▥"
+    ('\u{273D}', [0x14, 0x3E, 0x7F, 0x3E, 0x7F, 0x3E, 0x14, 0x00]), // "✽"
+    ('\u{2B05}', [0x3E, 0x67, 0x63, 0x81, 0x63, 0x67, 0x3E, 0x00]), // "⬅"
+    ('\u{2B06}', [0x3E, 0x77, 0x63, 0x41, 0x77, 0x77, 0x3E, 0x00]), // "⬆"
+    ('\u{27A1}', [0x3E, 0x73, 0x63, 0x81, 0x63, 0x73, 0x3E, 0x00]), // "➡"
+    ('\u{2B07}', [0x3E, 0x77, 0x77, 0x41, 0x63, 0x77, 0x3E, 0x00]), // "⬇"
+    ('\u{1F17E}', [0x3E, 0x63, 0x5D, 0x5D, 0x5D, 0x63, 0x3E, 0x00]), // "🅾"
+    ('\u{274E}', [0x3E, 0x5D, 0x6B, 0x77, 0x6B, 0x5D, 0x3E, 0x00]), // "❎"
+    ('\u{1F431}', [0x41, 0x63, 0x7F, 0x5D, 0x7F, 0x3E, 0x55, 0x00]), // "🐱"
+    ('\u{1F610}', [0x3E, 0x7F, 0x5D, 0x7F, 0x41, 0x7F, 0x3E, 0x00]), // "😐"
+    ('\u{25AE}', [0x1E, 0x1E, 0x1E, 0x1E, 0x1E, 0x1E, 0x1E, 0x00]), // "▮"
+    ('\u{25A0}', [0x00, 0x7E, 0x7E, 0x7E, 0x7E, 0x7E, 0x7E, 0x00]), // "■"
+    ('\u{25A1}', [0x00, 0x7E, 0x42, 0x42, 0x42, 0x42, 0x7E, 0x00]), // "□"
+    ('\u{25C0}', [0x20, 0x30, 0x38, 0x3C, 0x38, 0x30, 0x20, 0x00]), // "◀"
+    ('\u{25B6}', [0x04, 0x0C, 0x1C, 0x3C, 0x1C, 0x0C, 0x04, 0x00]), // "▶"
+];
+
+// Glyphs that fill the whole 9 pixel line so they join up vertically
+pub const FULL_CELL_GLYPHS: [(char, [u8; 9]); 14] = [
+    ('\u{2550}', [0x00, 0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0x00, 0x00]), // "═"
+    ('\u{2551}', [0x14, 0x14, 0x14, 0x14, 0x14, 0x14, 0x14, 0x14, 0x14]), // "║"
+    ('\u{2554}', [0x00, 0x00, 0x00, 0xFC, 0x04, 0xF4, 0x14, 0x14, 0x14]), // "╔"
+    ('\u{2557}', [0x00, 0x00, 0x00, 0x1F, 0x10, 0x17, 0x14, 0x14, 0x14]), // "╗"
+    ('\u{255A}', [0x14, 0x14, 0x14, 0xF4, 0x04, 0xFC, 0x00, 0x00, 0x00]), // "╚"
+    ('\u{255D}', [0x14, 0x14, 0x14, 0x17, 0x10, 0x1F, 0x00, 0x00, 0x00]), // "╝"
+    ('\u{2591}', [0x11, 0x00, 0x44, 0x00, 0x11, 0x00, 0x44, 0x00, 0x11]), // "░"
+    ('\u{2592}', [0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55]), // "▒"
+    ('\u{2593}', [0xEE, 0xFF, 0xBB, 0xFF, 0xEE, 0xFF, 0xBB, 0xFF, 0xEE]), // "▓"
+    ('\u{2588}', [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]), // "█"
+    ('\u{2580}', [0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00]), // "▀"
+    ('\u{2584}', [0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]), // "▄"
+    ('\u{258C}', [0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F]), // "▌"
+    ('\u{2590}', [0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0]), // "▐"
+];
+
+const BOX_UP: u8 = 1;
+const BOX_DOWN: u8 = 2;
+const BOX_LEFT: u8 = 4;
+const BOX_RIGHT: u8 = 8;
+
+pub const LIGHT_BOX_GLYPHS: [(char, u8); 11] = [
+    ('\u{2500}', BOX_LEFT | BOX_RIGHT),                     // "─"
+    ('\u{2502}', BOX_UP | BOX_DOWN),                        // "│"
+    ('\u{250C}', BOX_DOWN | BOX_RIGHT),                     // "┌"
+    ('\u{2510}', BOX_DOWN | BOX_LEFT),                      // "┐"
+    ('\u{2514}', BOX_UP | BOX_RIGHT),                       // "└"
+    ('\u{2518}', BOX_UP | BOX_LEFT),                        // "┘"
+    ('\u{251C}', BOX_UP | BOX_DOWN | BOX_RIGHT),            // "├"
+    ('\u{2524}', BOX_UP | BOX_DOWN | BOX_LEFT),             // "┤"
+    ('\u{252C}', BOX_DOWN | BOX_LEFT | BOX_RIGHT),          // "┬"
+    ('\u{2534}', BOX_UP | BOX_LEFT | BOX_RIGHT),            // "┴"
+    ('\u{253C}', BOX_UP | BOX_DOWN | BOX_LEFT | BOX_RIGHT), // "┼"
+];
+
+const GRAVE: [u8; 2] = [0x0C, 0x18];
+const ACUTE: [u8; 2] = [0x30, 0x18];
+const CIRCUMFLEX: [u8; 2] = [0x18, 0x24];
+const TILDE: [u8; 2] = [0x4C, 0x32];
+const DIAERESIS: [u8; 2] = [0x66, 0x00];
+const RING: [u8; 2] = [0x18, 0x18];
+const CEDILLA: u8 = 0x18;
+
+// Latin-1 letters built from a base glyph with a mark above it. Capitals keep
+// their full height and the mark sits above the line.
+pub const ACCENTED_GLYPHS: [(char, char, [u8; 2]); 51] = [
+    ('\u{C0}', 'A', GRAVE),      // "À"
+    ('\u{C1}', 'A', ACUTE),      // "Á"
+    ('\u{C2}', 'A', CIRCUMFLEX), // "Â"
+    ('\u{C3}', 'A', TILDE),      // "Ã"
+    ('\u{C4}', 'A', DIAERESIS),  // "Ä"
+    ('\u{C5}', 'A', RING),       // "Å"
+    ('\u{C8}', 'E', GRAVE),      // "È"
+    ('\u{C9}', 'E', ACUTE),      // "É"
+    ('\u{CA}', 'E', CIRCUMFLEX), // "Ê"
+    ('\u{CB}', 'E', DIAERESIS),  // "Ë"
+    ('\u{CC}', 'I', GRAVE),      // "Ì"
+    ('\u{CD}', 'I', ACUTE),      // "Í"
+    ('\u{CE}', 'I', CIRCUMFLEX), // "Î"
+    ('\u{CF}', 'I', DIAERESIS),  // "Ï"
+    ('\u{D1}', 'N', TILDE),      // "Ñ"
+    ('\u{D2}', 'O', GRAVE),      // "Ò"
+    ('\u{D3}', 'O', ACUTE),      // "Ó"
+    ('\u{D4}', 'O', CIRCUMFLEX), // "Ô"
+    ('\u{D5}', 'O', TILDE),      // "Õ"
+    ('\u{D6}', 'O', DIAERESIS),  // "Ö"
+    ('\u{D9}', 'U', GRAVE),      // "Ù"
+    ('\u{DA}', 'U', ACUTE),      // "Ú"
+    ('\u{DB}', 'U', CIRCUMFLEX), // "Û"
+    ('\u{DC}', 'U', DIAERESIS),  // "Ü"
+    ('\u{DD}', 'Y', ACUTE),      // "Ý"
+    ('\u{E0}', 'a', GRAVE),      // "à"
+    ('\u{E1}', 'a', ACUTE),      // "á"
+    ('\u{E2}', 'a', CIRCUMFLEX), // "â"
+    ('\u{E3}', 'a', TILDE),      // "ã"
+    ('\u{E4}', 'a', DIAERESIS),  // "ä"
+    ('\u{E5}', 'a', RING),       // "å"
+    ('\u{E8}', 'e', GRAVE),      // "è"
+    ('\u{E9}', 'e', ACUTE),      // "é"
+    ('\u{EA}', 'e', CIRCUMFLEX), // "ê"
+    ('\u{EB}', 'e', DIAERESIS),  // "ë"
+    ('\u{EC}', 'i', GRAVE),      // "ì"
+    ('\u{ED}', 'i', ACUTE),      // "í"
+    ('\u{EE}', 'i', CIRCUMFLEX), // "î"
+    ('\u{EF}', 'i', DIAERESIS),  // "ï"
+    ('\u{F1}', 'n', TILDE),      // "ñ"
+    ('\u{F2}', 'o', GRAVE),      // "ò"
+    ('\u{F3}', 'o', ACUTE),      // "ó"
+    ('\u{F4}', 'o', CIRCUMFLEX), // "ô"
+    ('\u{F5}', 'o', TILDE),      // "õ"
+    ('\u{F6}', 'o', DIAERESIS),  // "ö"
+    ('\u{F9}', 'u', GRAVE),      // "ù"
+    ('\u{FA}', 'u', ACUTE),      // "ú"
+    ('\u{FB}', 'u', CIRCUMFLEX), // "û"
+    ('\u{FC}', 'u', DIAERESIS),  // "ü"
+    ('\u{FD}', 'y', ACUTE),      // "ý"
+    ('\u{FF}', 'y', DIAERESIS),  // "ÿ"
+];
+
+// These take their mark in the empty bottom row instead
+pub const CEDILLA_GLYPHS: [(char, char); 2] = [
+    ('\u{C7}', 'C'), // "Ç"
+    ('\u{E7}', 'c'), // "ç"
+];
+
+pub const MISSING_GLYPH: [u8; 8] = [0x7E, 0x42, 0x42, 0x42, 0x42, 0x42, 0x7E, 0x00];
+pub const PICO8_MISSING_GLYPH: [u8; 5] = [0x07, 0x05, 0x05, 0x05, 0x07];
+
+// One row per entry, bit j of a row is the pixel in column j.
+#[derive(Clone, Debug)]
+pub struct Glyph {
+    pub width: i32,
+    pub height: i32,
+    pub x_offset: i32,
+    pub y_offset: i32,
+    pub advance: i32,
+    pub rows: Vec<u32>,
+}
+
+impl Glyph {
+    pub fn from_rows(rows: &[u8], width: i32, advance: i32) -> Glyph {
+        Glyph {
+            width,
+            height: rows.len() as i32,
+            x_offset: 0,
+            y_offset: 0,
+            advance,
+            rows: rows.iter().map(|row| *row as u32).collect(),
+        }
+    }
+
+    pub fn empty(advance: i32) -> Glyph {
+        Glyph {
+            width: 0,
+            height: 0,
+            x_offset: 0,
+            y_offset: 0,
+            advance,
+            rows: vec![],
+        }
+    }
+
+    fn light_box(lines: u8) -> Glyph {
+        let mut rows = vec![0; 9];
+        for (y, row) in rows.iter_mut().enumerate() {
+            for x in 0..8 {
+                let horizontal = y == 4
+                    && ((x <= 3 && lines & BOX_LEFT != 0) || (x >= 3 && lines & BOX_RIGHT != 0));
+                let vertical = x == 3
+                    && ((y <= 4 && lines & BOX_UP != 0) || (y >= 4 && lines & BOX_DOWN != 0));
+                if horizontal || vertical {
+                    *row |= 1 << x;
+                }
+            }
+        }
+        Glyph {
+            width: 8,
+            height: 9,
+            x_offset: 0,
+            y_offset: 0,
+            advance: 8,
+            rows,
+        }
+    }
+
+    fn with_accent(&self, accent: &[u8; 2], above_line: bool) -> Glyph {
+        let mut glyph = self.clone();
+        if above_line {
+            let mut rows: Vec<u32> = accent.iter().map(|row| *row as u32).collect();
+            rows.extend_from_slice(&self.rows);
+            glyph.rows = rows;
+            glyph.height += 2;
+            glyph.y_offset -= 2;
+        } else {
+            for (row, mark) in glyph.rows.iter_mut().zip(accent.iter()) {
+                *row = *mark as u32;
+            }
+        }
+        glyph
+    }
+
+    pub fn pixel(&self, x: i32, y: i32) -> bool {
+        x >= 0 && x < self.width && y >= 0 && y < self.height && (self.rows[y as usize] >> x) & 1 == 1
+    }
+}
+
+// y_offset of a glyph is measured from the top of the line, baseline is the
+// distance from the top of the line to the baseline.
+#[derive(Clone, Debug)]
+pub struct Font {
+    pub glyphs: HashMap<char, Glyph>,
+    pub kerning: HashMap<(char, char), i32>,
+    pub line_height: i32,
+    pub baseline: i32,
+    pub default_advance: i32,
+    pub tab_width: i32,
+    pub missing: Option<Glyph>,
+}
+
+impl Font {
+    pub fn new(line_height: i32, baseline: i32, default_advance: i32) -> Font {
+        Font {
+            glyphs: HashMap::new(),
+            kerning: HashMap::new(),
+            line_height,
+            baseline,
+            default_advance,
+            tab_width: default_advance * 4,
+            missing: None,
+        }
+    }
+
+    // Falls back to the missing glyph box for anything printable that the
+    // font doesn't cover.
+    pub fn glyph(&self, character: char) -> Option<&Glyph> {
+        match self.glyphs.get(&character) {
+            Some(glyph) => Some(glyph),
+            None if !character.is_control() => self.missing.as_ref(),
+            None => None,
+        }
+    }
+
+    pub fn advance(&self, character: char) -> i32 {
+        match self.glyph(character) {
+            Some(glyph) => glyph.advance,
+            None => self.default_advance,
+        }
+    }
+
+    // x is relative to the start of the line
+    pub fn next_tab_stop(&self, x: i32) -> i32 {
+        if self.tab_width <= 0 {
+            x + self.default_advance
+        } else {
+            (x.div_euclid(self.tab_width) + 1) * self.tab_width
+        }
+    }
+
+    pub fn add_glyph(&mut self, character: char, glyph: Glyph) {
+        self.glyphs.insert(character, glyph);
+    }
+
+    pub fn kerning(&self, left: char, right: char) -> i32 {
+        *self.kerning.get(&(left, right)).unwrap_or(&0)
+    }
+
+    fn from_ascii_table(
+        table: &[u8],
+        bytes_per_char: usize,
+        width: i32,
+        advance: i32,
+        line_height: i32,
+        baseline: i32,
+    ) -> Font {
+        let mut font = Font::new(line_height, baseline, advance);
+        font.glyphs.insert(' ', Glyph::empty(advance));
+        for (i, rows) in table.chunks(bytes_per_char).enumerate() {
+            let character = (33 + i as u8) as char;
+            font.glyphs
+                .insert(character, Glyph::from_rows(rows, width, advance));
+        }
+        font
+    }
+
+    pub fn bit_font() -> Font {
+        let mut font = Font::from_ascii_table(&BIT_FONT, BYTES_PER_CHAR, 8, 8, 9, 7);
+        for (character, rows) in EXTENDED_GLYPHS.iter() {
+            font.add_glyph(*character, Glyph::from_rows(rows, 8, 8));
+        }
+        for (character, rows) in FULL_CELL_GLYPHS.iter() {
+            font.add_glyph(*character, Glyph::from_rows(rows, 8, 8));
+        }
+        for (character, lines) in LIGHT_BOX_GLYPHS.iter() {
+            font.add_glyph(*character, Glyph::light_box(*lines));
+        }
+        for (character, base, accent) in ACCENTED_GLYPHS.iter() {
+            let glyph = font.glyphs[base].with_accent(accent, base.is_uppercase());
+            font.add_glyph(*character, glyph);
+        }
+        for (character, base) in CEDILLA_GLYPHS.iter() {
+            let mut glyph = font.glyphs[base].clone();
+            glyph.rows[7] = CEDILLA as u32;
+            font.add_glyph(*character, glyph);
+        }
+        // variation selector that trails the pico-8 button emoji
+        font.add_glyph('\u{FE0F}', Glyph::empty(0));
+        font.missing = Some(Glyph::from_rows(&MISSING_GLYPH, 8, 8));
+        font
+    }
+
+    pub fn pico8_font() -> Font {
+        let mut font = Font::from_ascii_table(&PICO8_FONT, PICO8_BYTES_PER_CHAR, 3, 4, 6, 5);
+        font.add_glyph('\u{FE0F}', Glyph::empty(0));
+        font.missing = Some(Glyph::from_rows(&PICO8_MISSING_GLYPH, 3, 4));
+        font
+    }
+}
+
+pub type FontHandle = usize;
+
+pub const DEFAULT_FONT: FontHandle = 0;
+pub const SMALL_FONT: FontHandle = 1;
+
+pub struct Fonts(pub RefCell<Vec<Font>>);
+unsafe impl Sync for Fonts {}
+
+pub static FONTS: Fonts = Fonts(RefCell::new(Vec::new()));
+
+fn init_builtin_fonts(fonts: &mut Vec<Font>) {
+    if fonts.is_empty() {
+        fonts.push(Font::bit_font());
+        fonts.push(Font::pico8_font());
+    }
+}
+
+pub fn register_font(font: Font) -> FontHandle {
+    let mut fonts = FONTS.0.borrow_mut();
+    init_builtin_fonts(&mut fonts);
+    fonts.push(font);
+    fonts.len() - 1
+}
+
+// Rows are u32 bit masks, so a glyph can't be wider than 32 pixels.
+pub const MAX_GLYPH_WIDTH: i32 = 32;
+
+// False for an unknown font or a glyph whose width doesn't fit its rows.
+pub fn register_glyph(font: FontHandle, character: char, glyph: Glyph) -> bool {
+    if glyph.width <= 0 || glyph.width > MAX_GLYPH_WIDTH {
+        return false;
+    }
+    let mut fonts = FONTS.0.borrow_mut();
+    init_builtin_fonts(&mut fonts);
+    if font < fonts.len() {
+        fonts[font].add_glyph(character, glyph);
+        true
+    } else {
+        false
+    }
+}
+
+// Runs f with the font behind the handle, falling back to the default font
+// for handles that were never registered.
+pub fn with_font<R, F: FnOnce(&Font) -> R>(font: FontHandle, f: F) -> R {
+    let mut fonts = FONTS.0.borrow_mut();
+    init_builtin_fonts(&mut fonts);
+    if font < fonts.len() {
+        f(&fonts[font])
+    } else {
+        f(&fonts[DEFAULT_FONT])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_glyph_rejects_widths_the_rows_cant_hold() {
+        let glyph = |width| Glyph {
+            width,
+            height: 1,
+            x_offset: 0,
+            y_offset: 0,
+            advance: width,
+            rows: vec![1],
+        };
+        assert!(!register_glyph(DEFAULT_FONT, 'a', glyph(0)));
+        assert!(!register_glyph(DEFAULT_FONT, 'a', glyph(-1)));
+        assert!(!register_glyph(DEFAULT_FONT, 'a', glyph(33)));
+        assert!(register_glyph(DEFAULT_FONT, '\u{E000}', glyph(32)));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::pico::*;
+use std::collections::VecDeque;
+
+// Distances are in screen pixels and times in the same milliseconds that
+// sketches get in update.
+#[derive(Copy, Clone, Debug)]
+pub struct GestureConfig {
+    // how far a pointer can wander and still count as a tap or long press
+    pub tap_max_distance: f32,
+    pub tap_max_duration: f32,
+    pub double_tap_interval: f32,
+    pub long_press_duration: f32,
+    // pixels per millisecond a drag has to end at to become a fling
+    pub fling_min_velocity: f32,
+    // how much of the end of a drag the fling velocity is measured over
+    pub velocity_window: f32,
+}
+
+impl Default for GestureConfig {
+    fn default() -> GestureConfig {
+        GestureConfig {
+            tap_max_distance: 4.0,
+            tap_max_duration: 250.0,
+            double_tap_interval: 300.0,
+            long_press_duration: 500.0,
+            fling_min_velocity: 0.3,
+            velocity_window: 100.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Gesture {
+    Tap { x: i32, y: i32 },
+    DoubleTap { x: i32, y: i32 },
+    LongPress { x: i32, y: i32 },
+    DragStart { x: i32, y: i32 },
+    Drag { x: i32, y: i32, dx: i32, dy: i32 },
+    DragEnd { x: i32, y: i32 },
+    // the center of two pointers moving together
+    Pan { x: f32, y: f32, dx: f32, dy: f32 },
+    // scale is distance / last_distance, around the center x, y
+    Pinch { x: f32, y: f32, scale: f32, distance: f32, last_distance: f32 },
+    // change in the angle between two pointers, in radians
+    Rotate { x: f32, y: f32, angle: f32 },
+    // pixels per millisecond when a drag was let go
+    Fling { vx: f32, vy: f32 },
+}
+
+pub struct GestureRecognizer {
+    pub config: GestureConfig,
+    down_time: Option<f32>,
+    down_pos: Point,
+    pos: Point,
+    dragging: bool,
+    long_pressed: bool,
+    // a second pointer joined, so this can't end as a tap, drag or fling
+    multi: bool,
+    last_tap: Option<(f32, Point)>,
+    samples: VecDeque<(f32, Point)>,
+}
+
+fn distance(a: Point, b: Point) -> f32 {
+    let (dx, dy) = ((b.x - a.x) as f32, (b.y - a.y) as f32);
+    (dx * dx + dy * dy).sqrt()
+}
+
+// Slots that have a position and the primary button (or a touch) down.
+fn contacts() -> Vec<(Point, Option<Point>)> {
+    (0..POINTER_COUNT)
+        .filter(|i| pointer_btn(*i as u8, 0))
+        .filter_map(|i| get_pointer_position(i).map(|pos| (pos, get_last_pointer_position(i))))
+        .collect()
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> GestureRecognizer {
+        GestureRecognizer {
+            config,
+            down_time: None,
+            down_pos: Point { x: 0, y: 0 },
+            pos: Point { x: 0, y: 0 },
+            dragging: false,
+            long_pressed: false,
+            multi: false,
+            last_tap: None,
+            samples: VecDeque::new(),
+        }
+    }
+
+    // Call once per update with the sketch's new_time.
+    pub fn update(&mut self, time: f32) -> Vec<Gesture> {
+        let mut gestures = vec![];
+        let contacts = contacts();
+        match contacts.len() {
+            0 => self.release(time, &mut gestures),
+            1 => {
+                let (pos, _) = contacts[0];
+                if self.down_time.is_none() {
+                    self.down_time = Some(time);
+                    self.down_pos = pos;
+                    self.pos = pos;
+                    self.dragging = false;
+                    self.long_pressed = false;
+                    self.samples.clear();
+                }
+                if !self.multi {
+                    self.single(time, pos, &mut gestures);
+                }
+                self.pos = pos;
+            }
+            _ => {
+                if self.down_time.is_none() {
+                    self.down_time = Some(time);
+                }
+                self.multi = true;
+                if self.dragging {
+                    self.dragging = false;
+                    gestures.push(Gesture::DragEnd {
+                        x: self.pos.x,
+                        y: self.pos.y,
+                    });
+                }
+                if let ((a, Some(last_a)), (b, Some(last_b))) = (contacts[0], contacts[1]) {
+                    two_pointer(a, last_a, b, last_b, &mut gestures);
+                }
+            }
+        }
+        gestures
+    }
+
+    fn single(&mut self, time: f32, pos: Point, gestures: &mut Vec<Gesture>) {
+        let down_time = self.down_time.unwrap_or(time);
+        if !self.dragging && distance(self.down_pos, pos) > self.config.tap_max_distance {
+            self.dragging = true;
+            gestures.push(Gesture::DragStart {
+                x: self.down_pos.x,
+                y: self.down_pos.y,
+            });
+            // the part of the drag the threshold swallowed
+            self.pos = self.down_pos;
+        }
+        if self.dragging && (pos.x != self.pos.x || pos.y != self.pos.y) {
+            gestures.push(Gesture::Drag {
+                x: pos.x,
+                y: pos.y,
+                dx: pos.x - self.pos.x,
+                dy: pos.y - self.pos.y,
+            });
+        }
+        if !self.dragging
+            && !self.long_pressed
+            && time - down_time >= self.config.long_press_duration
+        {
+            self.long_pressed = true;
+            gestures.push(Gesture::LongPress { x: pos.x, y: pos.y });
+        }
+        self.samples.push_back((time, pos));
+        while let Some((sample_time, _)) = self.samples.front() {
+            if time - sample_time > self.config.velocity_window {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn release(&mut self, time: f32, gestures: &mut Vec<Gesture>) {
+        let down_time = match self.down_time.take() {
+            Some(down_time) => down_time,
+            None => return,
+        };
+        let pos = self.pos;
+        if self.dragging {
+            gestures.push(Gesture::DragEnd { x: pos.x, y: pos.y });
+            if let (Some((start, from)), Some((end, to))) =
+                (self.samples.front(), self.samples.back())
+            {
+                let elapsed = end - start;
+                if elapsed > 0.0 {
+                    let vx = (to.x - from.x) as f32 / elapsed;
+                    let vy = (to.y - from.y) as f32 / elapsed;
+                    if (vx * vx + vy * vy).sqrt() >= self.config.fling_min_velocity {
+                        gestures.push(Gesture::Fling { vx, vy });
+                    }
+                }
+            }
+        } else if !self.multi
+            && !self.long_pressed
+            && time - down_time <= self.config.tap_max_duration
+        {
+            match self.last_tap {
+                Some((tap_time, tap_pos))
+                    if time - tap_time <= self.config.double_tap_interval
+                        && distance(tap_pos, pos) <= self.config.tap_max_distance =>
+                {
+                    self.last_tap = None;
+                    gestures.push(Gesture::DoubleTap { x: pos.x, y: pos.y });
+                }
+                _ => {
+                    self.last_tap = Some((time, pos));
+                    gestures.push(Gesture::Tap { x: pos.x, y: pos.y });
+                }
+            }
+        }
+        self.dragging = false;
+        self.long_pressed = false;
+        self.multi = false;
+        self.samples.clear();
+    }
+}
+
+fn two_pointer(a: Point, last_a: Point, b: Point, last_b: Point, gestures: &mut Vec<Gesture>) {
+    let center = |a: Point, b: Point| ((a.x + b.x) as f32 / 2.0, (a.y + b.y) as f32 / 2.0);
+    let (x, y) = center(a, b);
+    let (last_x, last_y) = center(last_a, last_b);
+    if x != last_x || y != last_y {
+        gestures.push(Gesture::Pan {
+            x,
+            y,
+            dx: x - last_x,
+            dy: y - last_y,
+        });
+    }
+    let (distance, last_distance) = (distance(a, b), distance(last_a, last_b));
+    if distance != last_distance && distance > 0.0 && last_distance > 0.0 {
+        gestures.push(Gesture::Pinch {
+            x,
+            y,
+            scale: distance / last_distance,
+            distance,
+            last_distance,
+        });
+    }
+    let angle = ((b.y - a.y) as f32).atan2((b.x - a.x) as f32);
+    let last_angle = ((last_b.y - last_a.y) as f32).atan2((last_b.x - last_a.x) as f32);
+    let mut delta = angle - last_angle;
+    // the short way round
+    if delta > std::f32::consts::PI {
+        delta -= 2.0 * std::f32::consts::PI;
+    } else if delta < -std::f32::consts::PI {
+        delta += 2.0 * std::f32::consts::PI;
+    }
+    if delta != 0.0 {
+        gestures.push(Gesture::Rotate { x, y, angle: delta });
+    }
+}
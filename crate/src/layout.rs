@@ -0,0 +1,307 @@
+use crate::font::*;
+use crate::markup::escape_markup;
+use crate::pico::*;
+use std::cmp;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum HAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum VAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl HAlign {
+    // 0 left, 1 center, 2 right
+    pub fn from_code(code: u32) -> HAlign {
+        match code {
+            1 => HAlign::Center,
+            2 => HAlign::Right,
+            _ => HAlign::Left,
+        }
+    }
+}
+
+impl VAlign {
+    // 0 top, 1 middle, 2 bottom
+    pub fn from_code(code: u32) -> VAlign {
+        match code {
+            1 => VAlign::Middle,
+            2 => VAlign::Bottom,
+            _ => VAlign::Top,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TextLayout {
+    pub h_align: HAlign,
+    pub v_align: VAlign,
+    pub wrap: bool,
+    pub ellipsis: bool,
+}
+
+impl Default for TextLayout {
+    fn default() -> TextLayout {
+        TextLayout {
+            h_align: HAlign::Left,
+            v_align: VAlign::Top,
+            wrap: true,
+            ellipsis: true,
+        }
+    }
+}
+
+const ELLIPSIS: &str = "...";
+
+pub fn line_width(font: &Font, line: &str) -> i32 {
+    let mut width = 0;
+    let mut previous = None;
+    for character in line.chars() {
+        if character == '\r' {
+            continue;
+        } else if character == '\t' {
+            width = font.next_tab_stop(width);
+            previous = None;
+            continue;
+        }
+        if let Some(previous) = previous {
+            width += font.kerning(previous, character);
+        }
+        width += font.advance(character);
+        previous = Some(character);
+    }
+    width
+}
+
+// Width of the widest line and the height of all lines of plain text at the
+// font's own size, before any w/h scale passed to prnt.
+pub fn text_size(string: &str, font: FontHandle) -> (i32, i32) {
+    with_font(font, |font| {
+        let mut width = 0;
+        let mut lines = 0;
+        for line in string.split('\n') {
+            width = cmp::max(width, line_width(font, line));
+            lines += 1;
+        }
+        (width, lines * font.line_height)
+    })
+}
+
+// Longest prefix of line that fits in max_width, always at least one
+// character so wrapping makes progress.
+fn split_to_fit<'a>(font: &Font, line: &'a str, max_width: i32) -> (&'a str, &'a str) {
+    let mut end = 0;
+    for (i, character) in line.char_indices() {
+        let next = i + character.len_utf8();
+        if end > 0 && line_width(font, &line[..next]) > max_width {
+            break;
+        }
+        end = next;
+    }
+    line.split_at(end)
+}
+
+fn wrap_paragraph(font: &Font, paragraph: &str, max_width: i32, lines: &mut Vec<String>) {
+    let mut current = String::new();
+    for word in paragraph.split(' ') {
+        let candidate = if current.is_empty() {
+            word.to_owned()
+        } else {
+            format!("{} {}", current, word)
+        };
+        if line_width(font, &candidate) <= max_width {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(current);
+        }
+        let mut rest = word;
+        while !rest.is_empty() && line_width(font, rest) > max_width {
+            let (head, tail) = split_to_fit(font, rest, max_width);
+            lines.push(head.to_owned());
+            rest = tail;
+        }
+        current = rest.to_owned();
+    }
+    lines.push(current);
+}
+
+fn with_ellipsis(font: &Font, line: &str, max_width: i32) -> String {
+    let mut line = line.trim_end().to_owned();
+    loop {
+        let candidate = format!("{}{}", line, ELLIPSIS);
+        if line_width(font, &candidate) <= max_width {
+            return candidate;
+        }
+        if line.pop().is_none() {
+            break;
+        }
+        line = line.trim_end().to_owned();
+    }
+    let (dots, _) = split_to_fit(font, ELLIPSIS, max_width);
+    if line_width(font, dots) <= max_width {
+        dots.to_owned()
+    } else {
+        String::new()
+    }
+}
+
+pub fn layout_lines(
+    string: &str,
+    font: &Font,
+    width: i32,
+    height: i32,
+    layout: &TextLayout,
+) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in string.split('\n') {
+        let paragraph = paragraph.trim_end_matches('\r');
+        if layout.wrap {
+            wrap_paragraph(font, paragraph, width, &mut lines);
+        } else if layout.ellipsis && line_width(font, paragraph) > width {
+            lines.push(with_ellipsis(font, paragraph, width));
+        } else {
+            lines.push(paragraph.to_owned());
+        }
+    }
+    let max_lines = if font.line_height > 0 {
+        cmp::max(height / font.line_height, 0) as usize
+    } else {
+        lines.len()
+    };
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if layout.ellipsis {
+            if let Some(last) = lines.pop() {
+                lines.push(with_ellipsis(font, &last, width));
+            }
+        }
+    }
+    lines
+}
+
+// Draws string inside the box at x, y with the given width and height and
+// returns the cursor after the last character drawn. Nothing is drawn outside
+// the box. string is plain text, any markup in it is drawn as is. w and h
+// scale the font like they do for prnt.
+pub fn prnt_layout(
+    string: &str,
+    font: FontHandle,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    layout: &TextLayout,
+    w: i32,
+    h: i32,
+    c: i32,
+) -> Point {
+    let (w, h) = (cmp::max(w, 1), cmp::max(h, 1));
+    let (lines, line_height) = with_font(font, |font_data| {
+        let lines = layout_lines(string, font_data, width / w, height / h, layout);
+        let widths: Vec<i32> = lines
+            .iter()
+            .map(|line| line_width(font_data, line) * w)
+            .collect();
+        (
+            lines.into_iter().zip(widths.into_iter()).collect::<Vec<_>>(),
+            font_data.line_height * h,
+        )
+    });
+    let total_height = lines.len() as i32 * line_height;
+    let mut _y = y + match layout.v_align {
+        VAlign::Top => 0,
+        VAlign::Middle => (height - total_height) / 2,
+        VAlign::Bottom => height - total_height,
+    };
+    let clip = get_clip();
+    let l = cmp::max(x, clip.l);
+    let t = cmp::max(y, clip.t);
+    let r = cmp::min(x + width, clip.r);
+    let b = cmp::min(y + height, clip.b);
+    set_clip(l, t, cmp::max(r - l, 0), cmp::max(b - t, 0));
+    let mut cursor = Point { x, y: _y };
+    for (line, line_width) in lines.iter() {
+        let _x = x + match layout.h_align {
+            HAlign::Left => 0,
+            HAlign::Center => (width - line_width) / 2,
+            HAlign::Right => width - line_width,
+        };
+        cursor = prnt(&escape_markup(line), font, _x, _y, w, h, c);
+        _y += line_height;
+    }
+    set_clip(clip.l, clip.t, clip.r - clip.l, clip.b - clip.t);
+    cursor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every character is 4 wide, lines are 8 high
+    fn font() -> Font {
+        Font::new(8, 6, 4)
+    }
+
+    fn layout(wrap: bool, ellipsis: bool) -> TextLayout {
+        TextLayout {
+            wrap,
+            ellipsis,
+            ..TextLayout::default()
+        }
+    }
+
+    #[test]
+    fn wraps_at_spaces() {
+        assert_eq!(
+            layout_lines("aa bb cc\ndd", &font(), 20, 100, &layout(true, true)),
+            vec!["aa bb", "cc", "dd"]
+        );
+    }
+
+    #[test]
+    fn breaks_words_wider_than_the_box() {
+        assert_eq!(
+            layout_lines("a abcdefgh", &font(), 12, 100, &layout(true, true)),
+            vec!["a", "abc", "def", "gh"]
+        );
+    }
+
+    #[test]
+    fn cuts_long_lines_with_an_ellipsis() {
+        assert_eq!(
+            layout_lines("abcdefgh", &font(), 20, 100, &layout(false, true)),
+            vec!["ab..."]
+        );
+        assert_eq!(
+            layout_lines("abcdefgh", &font(), 20, 100, &layout(false, false)),
+            vec!["abcdefgh"]
+        );
+        // too narrow for all three dots
+        assert_eq!(
+            layout_lines("abcdefgh", &font(), 9, 100, &layout(false, true)),
+            vec![".."]
+        );
+    }
+
+    #[test]
+    fn drops_lines_below_the_box() {
+        assert_eq!(
+            layout_lines("aa bb cc", &font(), 12, 16, &layout(true, true)),
+            vec!["aa", "..."]
+        );
+        assert_eq!(
+            layout_lines("aa bb cc", &font(), 12, 16, &layout(true, false)),
+            vec!["aa", "bb"]
+        );
+        assert!(layout_lines("aa", &font(), 12, 7, &layout(true, true)).is_empty());
+    }
+}
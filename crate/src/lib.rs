@@ -4,8 +4,25 @@ extern crate web_sys;
 extern crate ncollide2d;
 extern crate nalgebra;
 extern crate euclid;
-mod font;
-mod pico;
+extern crate png;
+extern crate gif;
+pub mod asset;
+mod bdf;
+pub mod bytes;
+pub mod color;
+pub mod convert;
+pub mod display_list;
+mod document;
+pub mod font;
+pub mod gesture;
+pub mod layout;
+mod markup;
+mod p8;
+mod palette_format;
+mod palettes;
+pub mod pico;
+pub mod recorder;
+mod screenshot;
 mod sketch;
 mod sketches;
 
@@ -33,6 +50,7 @@ pub fn init(index: usize) {
             palette[i] = DEFAULT_COLORS[i];
         }
     }
+    palettes::fill_unused(16);
     {
         let mut palette_swap = PALETTE_SWAP.0.borrow_mut();
         for i in 0..NUM_COLORS {
@@ -81,6 +99,7 @@ pub fn update(delta: f32) {
     if let Some(sketch) = active.as_ref() {
         (*sketch).borrow_mut().update(old, new);
     }
+    recorder::capture_frame(delta);
     for i in STATE.0.borrow_mut().mouse_buttons.iter_mut() {
         *i = match *i {
             MouseButtonState::UpThisFrame => MouseButtonState::Up,
@@ -99,10 +118,8 @@ pub fn update(delta: f32) {
         state.pointer_state_changed = false;
     }
 
-    {
-        let mut state = STATE.0.borrow_mut();
-        state.scroll_delta = 0.0;
-    }
+    clear_wheel_deltas();
+    clear_pointer_events();
     // rect_fill(1, 10, 126, -127, 12);
     // for _ in 0..1 {
     //     let x0: u32 = rand::random();
@@ -153,28 +170,89 @@ pub fn update(delta: f32) {
     // }
 }
 
+// Pointers are identified by the host's pointer id, begin_pointer gives the
+// id a slot and returns it, or -1 when all the slots are taken. The other
+// pointer functions ignore ids without a slot.
 #[bindgen]
-pub fn set_pointer_pos(idx: usize, x: i32, y: i32) {
-    let mut state = STATE.0.borrow_mut();
-    if idx < (*state).pointer_pos.len() {
+pub fn begin_pointer(id: i32, x: i32, y: i32, time: f64) -> i32 {
+    pico::begin_pointer(id, x, y, time).map_or(-1, |slot| slot as i32)
+}
+
+#[bindgen]
+pub fn end_pointer(id: i32, time: f64) {
+    pico::end_pointer(id, time);
+}
+
+#[bindgen]
+pub fn get_pointer_slot(id: i32) -> i32 {
+    pico::get_pointer_slot(id).map_or(-1, |slot| slot as i32)
+}
+
+#[bindgen]
+pub fn set_pointer_pos(id: i32, x: i32, y: i32) {
+    if let Some(idx) = pico::get_pointer_slot(id) {
+        let mut state = STATE.0.borrow_mut();
         (*state).pointer_pos[idx] = Some(Point { x: x, y: y });
         state.pointer_pos_changed = true;
     }
 }
 
+// kind is 0 for move, 1 for down and 2 for up.
 #[bindgen]
-pub fn set_pointer_end(idx: usize) {
-    let mut state = STATE.0.borrow_mut();
-    if idx < (*state).pointer_pos.len() {
-        (*state).pointer_pos[idx] = None;
-        state.pointer_pos_changed = true;
+pub fn push_pointer_event(
+    id: i32,
+    kind: u8,
+    x: i32,
+    y: i32,
+    buttons: u32,
+    pressure: f32,
+    time: f64,
+) {
+    if let (Some(idx), Some(kind)) = (
+        pico::get_pointer_slot(id),
+        PointerEventKind::from_code(kind),
+    ) {
+        pico::push_pointer_event(PointerEvent {
+            kind,
+            pointer: idx,
+            id,
+            x,
+            y,
+            buttons,
+            pressure,
+            time,
+        });
     }
 }
 
 #[bindgen]
-pub fn set_pointer_state(idx: usize, btns: u32) {
-    let mut state = STATE.0.borrow_mut();
-    if idx < (*state).pointer_state.len() {
+pub fn set_pointer_info(
+    id: i32,
+    pressure: f32,
+    tilt_x: f32,
+    tilt_y: f32,
+    twist: f32,
+    width: f32,
+    height: f32,
+    pointer_type: &str,
+) {
+    if let Some(idx) = pico::get_pointer_slot(id) {
+        STATE.0.borrow_mut().pointer_info[idx] = PointerInfo {
+            pressure,
+            tilt_x,
+            tilt_y,
+            twist,
+            width,
+            height,
+            pointer_type: PointerType::from_name(pointer_type),
+        };
+    }
+}
+
+#[bindgen]
+pub fn set_pointer_state(id: i32, btns: u32) {
+    if let Some(idx) = pico::get_pointer_slot(id) {
+        let mut state = STATE.0.borrow_mut();
         (*state).pointer_state[idx] = btns;
         state.pointer_state_changed = true;
     }
@@ -217,6 +295,47 @@ pub fn screen_size() -> usize {
     WIDTH() * HEIGHT()
 }
 
+#[bindgen]
+pub fn dirty_rect() -> Vec<i32> {
+    match get_dirty_rect() {
+        Some(ClipRect { l, t, r, b }) => vec![l, t, r, b],
+        None => vec![],
+    }
+}
+
+#[bindgen]
+pub fn reset_dirty_rect() {
+    pico::reset_dirty_rect();
+}
+
+#[bindgen]
+pub fn export_png(target: u8, scale: usize) -> Result<Vec<u8>, JsValue> {
+    if target > 3 {
+        return Err(JsValue::from_str(&format!("screen index {} is not valid", target)));
+    }
+    screenshot::export_png(target, scale).map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+#[bindgen]
+pub fn start_recording(max_frames: usize) {
+    recorder::start_recording(max_frames);
+}
+
+#[bindgen]
+pub fn is_recording() -> bool {
+    recorder::is_recording()
+}
+
+#[bindgen]
+pub fn recorded_frames() -> usize {
+    recorder::recorded_frames()
+}
+
+#[bindgen]
+pub fn stop_recording() -> Result<Vec<u8>, JsValue> {
+    recorder::stop_recording().map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
 #[bindgen]
 pub fn palette_size() -> usize {
     NUM_COLORS * 3
@@ -282,14 +401,201 @@ pub fn get_sketch_is_public(i: usize) -> Option<bool> {
     }
 }
 
+// Every sketch's metadata as a json array, see SketchDescriptor::to_json.
+#[bindgen]
+pub fn get_sketch_catalog() -> String {
+    catalog_json()
+}
+
+// The index of the sketch with this url slug, or -1.
+#[bindgen]
+pub fn find_sketch(url: &str) -> i32 {
+    sketches::find_sketch(url).map_or(-1, |i| i as i32)
+}
+
+// The sketch's thumbnail as a png, if it has one that parses.
+#[bindgen]
+pub fn get_sketch_thumbnail(i: usize) -> Option<Vec<u8>> {
+    let bytes = SKETCHES.get(i)?.thumbnail?;
+    let asset = asset::parse_pico(bytes).ok()?;
+    screenshot::asset_png(&asset).ok()
+}
+
+// rows are bit masks with bit j set for a pixel in column j
+#[bindgen]
+pub fn register_glyph(font: usize, code: u32, width: i32, advance: i32, rows: Vec<u32>) -> bool {
+    match std::char::from_u32(code) {
+        Some(character) => font::register_glyph(
+            font,
+            character,
+            font::Glyph {
+                width,
+                height: rows.len() as i32,
+                x_offset: 0,
+                y_offset: 0,
+                advance,
+                rows,
+            },
+        ),
+        None => false,
+    }
+}
+
+#[bindgen]
+pub fn load_bdf_font(bytes: &[u8]) -> Result<usize, JsValue> {
+    bdf::load_bdf_font(bytes).map_err(|err| JsValue::from(err.to_string()))
+}
+
+// [width, height] of string drawn at 1x in font
+#[bindgen]
+pub fn text_size(string: &str, font: usize) -> Vec<i32> {
+    let (width, height) = layout::text_size(string, font);
+    vec![width, height]
+}
+
+// Draws string wrapped or cut to fit the box, see HAlign and VAlign for the
+// alignment codes. Returns the cursor after the last character as [x, y].
+#[bindgen]
+pub fn prnt_layout(
+    string: &str,
+    font: usize,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    h_align: u32,
+    v_align: u32,
+    wrap: bool,
+    ellipsis: bool,
+    c: i32,
+) -> Vec<i32> {
+    let layout = layout::TextLayout {
+        h_align: layout::HAlign::from_code(h_align),
+        v_align: layout::VAlign::from_code(v_align),
+        wrap,
+        ellipsis,
+    };
+    let Point { x, y } = layout::prnt_layout(string, font, x, y, width, height, &layout, 1, 1, c);
+    vec![x, y]
+}
+
+#[bindgen]
+pub fn load_spritesheet(bytes: &[u8]) -> Result<(), JsValue> {
+    asset::load_spritesheet(bytes).map_err(|err| JsValue::from(err.to_string()))
+}
+
+#[bindgen]
+pub fn get_palette_count() -> usize {
+    palettes::PALETTES.len()
+}
+
+#[bindgen]
+pub fn get_palette_name(i: usize) -> Option<String> {
+    palettes::PALETTES.get(i).map(|palette| palette.name.into())
+}
+
+// Returns how many colors were set, 0 if there is no palette with that name.
+#[bindgen]
+pub fn set_named_palette(name: &str, offset: usize) -> usize {
+    match palettes::find_palette(name) {
+        Some(palette) => palettes::apply_palette(palette, offset),
+        None => 0,
+    }
+}
+
+#[bindgen]
+pub fn fill_unused_palette(first: usize) {
+    palettes::fill_unused(first);
+}
+
+// Nearest of the first count palette entries, perceptual unless rgb is true.
+#[bindgen]
+pub fn nearest_color(r: u8, g: u8, b: u8, count: usize, rgb: bool) -> u8 {
+    let metric = if rgb {
+        color::ColorMetric::Rgb
+    } else {
+        color::ColorMetric::Lab
+    };
+    color::nearest_color_cached(&[r, g, b], count, metric)
+}
+
+#[bindgen]
+pub fn sort_by_luminance(indices: Vec<u8>) -> Vec<u8> {
+    color::sort_by_luminance(&indices)
+}
+
+#[bindgen]
+pub fn fill_palette_ramp(from: u8, to: u8) {
+    color::fill_ramp(from, to);
+}
+
+#[bindgen]
+pub fn palette_ramp(from: u8, to: u8, steps: usize, count: usize) -> Vec<u8> {
+    color::ramp_indices(from, to, steps, count)
+}
+
+// The active sketch's canvas as document bytes, if it supports saving.
+#[bindgen]
+pub fn save_document() -> Option<Vec<u8>> {
+    let active = ACTIVE_SKETCH.0.borrow();
+    let document = active.as_ref()?.borrow().save()?;
+    Some(document.serialize())
+}
+
+// The active sketch's strokes as an svg document, if it supports saving.
+#[bindgen]
+pub fn export_svg() -> Option<String> {
+    let active = ACTIVE_SKETCH.0.borrow();
+    let document = active.as_ref()?.borrow().save()?;
+    Some(document.to_svg())
+}
+
+#[bindgen]
+pub fn load_document(bytes: &[u8]) -> Result<(), JsValue> {
+    let document = document::Document::deserialize(bytes)
+        .map_err(|err| JsValue::from(format!("not a valid document: {}", err)))?;
+    let active = ACTIVE_SKETCH.0.borrow();
+    match active.as_ref() {
+        Some(sketch) if sketch.borrow_mut().load(&document) => Ok(()),
+        _ => Err(JsValue::from("the active sketch can't load this document")),
+    }
+}
+
+fn palette_format_for(format: &str, bytes: &[u8]) -> palette_format::PaletteFormat {
+    palette_format::PaletteFormat::from_extension(format)
+        .unwrap_or_else(|| palette_format::PaletteFormat::detect(bytes))
+}
+
+// format is a file extension (hex, gpl, pal or act), anything else is
+// detected from the contents.
+#[bindgen]
+pub fn load_palette(bytes: &[u8], format: &str) -> Result<usize, JsValue> {
+    palette_format::load_palette(bytes, palette_format_for(format, bytes))
+        .map_err(|err| JsValue::from(err.to_string()))
+}
+
+#[bindgen]
+pub fn save_palette(format: &str, count: usize) -> Result<Vec<u8>, JsValue> {
+    match palette_format::PaletteFormat::from_extension(format) {
+        Some(format) => Ok(palette_format::save_palette(format, count)),
+        None => Err(JsValue::from(format!("unknown palette format '{}'", format))),
+    }
+}
+
+#[bindgen]
+pub fn load_p8(bytes: &[u8]) -> Result<(), JsValue> {
+    p8::load_p8(bytes)
+        .map(|_| ())
+        .map_err(|err| JsValue::from(err.to_string()))
+}
+
 #[bindgen]
 pub fn get_memory() -> JsValue {
     wasm_bindgen::memory()
 }
 
+// Takes a WheelEvent's deltas as they are, mode is its deltaMode.
 #[bindgen]
-pub fn set_wheel(delta: f64) {
-    let mut state = STATE.0.borrow_mut();
-    (*state).scroll += delta;
-    (*state).scroll_delta = delta;
+pub fn set_wheel(dx: f64, dy: f64, dz: f64, mode: u32, ctrl: bool) {
+    add_wheel(dx, dy, dz, WheelMode::from_code(mode), ctrl);
 }
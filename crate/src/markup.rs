@@ -0,0 +1,200 @@
+// Markup understood by prnt:
+//   {c:7}        text color
+//   {bg:1}       background color behind each character, {bg:-} turns it off
+//   {outline:0}  outline color, {outline:-} turns it off
+//   {shadow:5}   drop shadow color, {shadow:-} turns it off
+//   {size:3x4}   scale of each font pixel, the same as prnt's w and h
+//   {/}          back to the style prnt was called with
+//   {{           a literal {
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TextStyle {
+    pub color: i32,
+    pub background: Option<i32>,
+    pub outline: Option<i32>,
+    pub shadow: Option<i32>,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl TextStyle {
+    pub fn new(w: i32, h: i32, c: i32) -> TextStyle {
+        TextStyle {
+            color: c,
+            background: None,
+            outline: None,
+            shadow: None,
+            w,
+            h,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct StyledSpan {
+    pub style: TextStyle,
+    pub text: String,
+}
+
+pub fn escape_markup(string: &str) -> String {
+    string.replace('{', "{{")
+}
+
+fn parse_optional_color(value: &str) -> Option<Option<i32>> {
+    if value == "-" {
+        Some(None)
+    } else {
+        value.parse::<i32>().ok().map(Some)
+    }
+}
+
+fn apply_tag(tag: &str, style: &mut TextStyle, base: &TextStyle) -> bool {
+    if tag == "/" {
+        *style = *base;
+        return true;
+    }
+    let mut parts = tag.splitn(2, ':');
+    let name = parts.next().unwrap_or("");
+    let value = match parts.next() {
+        Some(value) => value.trim(),
+        None => return false,
+    };
+    match name.trim() {
+        "c" => match value.parse::<i32>() {
+            Ok(color) => style.color = color,
+            Err(_) => return false,
+        },
+        "bg" => match parse_optional_color(value) {
+            Some(color) => style.background = color,
+            None => return false,
+        },
+        "outline" => match parse_optional_color(value) {
+            Some(color) => style.outline = color,
+            None => return false,
+        },
+        "shadow" => match parse_optional_color(value) {
+            Some(color) => style.shadow = color,
+            None => return false,
+        },
+        "size" => {
+            let mut size = value.splitn(2, 'x');
+            let w = size.next().and_then(|w| w.parse::<i32>().ok());
+            let h = size.next().and_then(|h| h.parse::<i32>().ok());
+            match (w, h) {
+                (Some(w), _) if w < 1 => return false,
+                (_, Some(h)) if h < 1 => return false,
+                (Some(w), Some(h)) => {
+                    style.w = w;
+                    style.h = h;
+                }
+                (Some(w), None) => {
+                    style.w = w;
+                    style.h = w;
+                }
+                _ => return false,
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
+// Tags that don't parse are kept as literal text.
+pub fn parse_markup(markup: &str, base: &TextStyle) -> Vec<StyledSpan> {
+    let mut spans = vec![];
+    let mut style = *base;
+    let mut text = String::new();
+    let mut rest = markup;
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with("{{") {
+            text.push('{');
+            rest = &rest[2..];
+            continue;
+        }
+        let end = match rest.find('}') {
+            Some(end) => end,
+            None => break,
+        };
+        let mut new_style = style;
+        if apply_tag(&rest[1..end], &mut new_style, base) {
+            if !text.is_empty() {
+                spans.push(StyledSpan {
+                    style,
+                    text: text.split_off(0),
+                });
+            }
+            style = new_style;
+        } else {
+            text.push_str(&rest[..(end + 1)]);
+        }
+        rest = &rest[(end + 1)..];
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        spans.push(StyledSpan { style, text });
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(style: TextStyle, text: &str) -> StyledSpan {
+        StyledSpan {
+            style,
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn tags_stack_until_reset() {
+        let base = TextStyle::new(1, 1, 7);
+        let red = TextStyle { color: 8, ..base };
+        let red_on_blue = TextStyle {
+            background: Some(1),
+            ..red
+        };
+        assert_eq!(
+            parse_markup("a{c:8}b{bg:1}c{bg:-}d{/}e", &base),
+            vec![
+                span(base, "a"),
+                span(red, "b"),
+                span(red_on_blue, "c"),
+                span(red, "d"),
+                span(base, "e"),
+            ]
+        );
+    }
+
+    #[test]
+    fn size_sets_both_scales() {
+        let base = TextStyle::new(1, 1, 7);
+        let spans = parse_markup("{size:2x3}a{size:4}b", &base);
+        assert_eq!((spans[0].style.w, spans[0].style.h), (2, 3));
+        assert_eq!((spans[1].style.w, spans[1].style.h), (4, 4));
+    }
+
+    #[test]
+    fn unclosed_tags_are_text() {
+        let base = TextStyle::new(1, 1, 7);
+        assert_eq!(parse_markup("a{c:8", &base), vec![span(base, "a{c:8")]);
+    }
+
+    #[test]
+    fn unknown_and_invalid_tags_are_text() {
+        let base = TextStyle::new(1, 1, 7);
+        for markup in &["{blink:1}x", "{c:red}x", "{size:0x2}x", "{c}x", "{}x"] {
+            assert_eq!(parse_markup(markup, &base), vec![span(base, markup)]);
+        }
+    }
+
+    #[test]
+    fn escaped_braces_are_literal() {
+        let base = TextStyle::new(1, 1, 7);
+        let text = "fn main() {c:8}";
+        assert_eq!(parse_markup(&escape_markup(text), &base), vec![span(base, text)]);
+    }
+}
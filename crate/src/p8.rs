@@ -0,0 +1,335 @@
+use crate::asset::*;
+use crate::palettes::PICO8_EXTENDED;
+use std::fmt;
+use std::str;
+
+// Reads the graphics parts of PICO-8's text cartridge format:
+//   __gfx__    128 lines of 128 hex digits, one pixel each
+//   __gff__    2 lines of 256 hex digits, one flag byte per sprite
+//   __map__    32 lines of 256 hex digits, one tile byte per cell
+//   __label__  128 lines of 128 digits, 0-9 and a-v for all 32 colors
+// The bottom half of the 128x64 map shares memory with the bottom half of
+// the spritesheet, so it's read from __gfx__ rows 64 to 127.
+
+pub const P8_SHEET_SIZE: usize = 128;
+pub const P8_MAP_WIDTH: usize = 128;
+pub const P8_MAP_HEIGHT: usize = 64;
+const P8_MAP_TEXT_ROWS: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum P8Error {
+    NotUtf8,
+    MissingHeader,
+    TooManyLines { section: &'static str, line: usize },
+    WrongLength { section: &'static str, line: usize, expected: usize, found: usize },
+    InvalidDigit { section: &'static str, line: usize, digit: char },
+    Asset(AssetError),
+}
+
+impl fmt::Display for P8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            P8Error::NotUtf8 => write!(f, "cartridge is not valid utf-8"),
+            P8Error::MissingHeader => write!(f, "cartridge does not start with 'pico-8 cartridge'"),
+            P8Error::TooManyLines { section, line } => {
+                write!(f, "line {}: too many lines in __{}__", line, section)
+            }
+            P8Error::WrongLength {
+                section,
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: __{}__ lines are {} characters, found {}",
+                line, section, expected, found
+            ),
+            P8Error::InvalidDigit {
+                section,
+                line,
+                digit,
+            } => write!(f, "line {}: '{}' is not valid in __{}__", line, digit, section),
+            P8Error::Asset(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct P8Cart {
+    // 128x128 color indices
+    pub gfx: Vec<u8>,
+    pub flags: Vec<u8>,
+    pub map: TileMap,
+    // 128x128 color indices, 16 to 31 are the extra colors
+    pub label: Option<Vec<u8>>,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Section {
+    Gfx,
+    Gff,
+    Map,
+    Label,
+    Other,
+}
+
+impl Section {
+    fn name(self) -> &'static str {
+        match self {
+            Section::Gfx => "gfx",
+            Section::Gff => "gff",
+            Section::Map => "map",
+            Section::Label => "label",
+            Section::Other => "",
+        }
+    }
+
+    // (line length, line count, digits per value, highest digit)
+    fn shape(self) -> (usize, usize, usize, u32) {
+        match self {
+            Section::Gfx => (P8_SHEET_SIZE, P8_SHEET_SIZE, 1, 16),
+            Section::Gff => (256, 2, 2, 16),
+            Section::Map => (P8_MAP_WIDTH * 2, P8_MAP_TEXT_ROWS, 2, 16),
+            Section::Label => (P8_SHEET_SIZE, P8_SHEET_SIZE, 1, 32),
+            Section::Other => (0, 0, 1, 0),
+        }
+    }
+}
+
+fn section_for(header: &str) -> Option<Section> {
+    if !(header.starts_with("__") && header.ends_with("__") && header.len() > 4) {
+        return None;
+    }
+    Some(match &header[2..(header.len() - 2)] {
+        "gfx" => Section::Gfx,
+        "gff" => Section::Gff,
+        "map" => Section::Map,
+        "label" => Section::Label,
+        _ => Section::Other,
+    })
+}
+
+fn parse_line(section: Section, line: usize, content: &str) -> Result<Vec<u8>, P8Error> {
+    let (length, _, digits, radix) = section.shape();
+    let found = content.chars().count();
+    if found != length {
+        return Err(P8Error::WrongLength {
+            section: section.name(),
+            line,
+            expected: length,
+            found,
+        });
+    }
+    let values: Vec<u32> = content
+        .chars()
+        .map(|digit| {
+            digit.to_digit(radix).ok_or(P8Error::InvalidDigit {
+                section: section.name(),
+                line,
+                digit,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(values
+        .chunks(digits)
+        .map(|value| value.iter().fold(0, |byte, digit| byte * radix + digit) as u8)
+        .collect())
+}
+
+pub fn parse_p8(bytes: &[u8]) -> Result<P8Cart, P8Error> {
+    let text = str::from_utf8(bytes).map_err(|_| P8Error::NotUtf8)?;
+    let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l.trim()));
+    match lines.next() {
+        Some((_, header)) if header.starts_with("pico-8 cartridge") => {}
+        _ => return Err(P8Error::MissingHeader),
+    }
+
+    let mut gfx = vec![0; P8_SHEET_SIZE * P8_SHEET_SIZE];
+    let mut flags = vec![0; SPRITE_COUNT];
+    let mut map_rows = vec![0; P8_MAP_WIDTH * P8_MAP_TEXT_ROWS];
+    let mut label: Option<Vec<u8>> = None;
+    let mut section = Section::Other;
+    let mut row = 0;
+    for (line, content) in lines {
+        if let Some(next) = section_for(content) {
+            section = next;
+            row = 0;
+            if section == Section::Label {
+                label = Some(vec![0; P8_SHEET_SIZE * P8_SHEET_SIZE]);
+            }
+            continue;
+        }
+        if section == Section::Other || content.is_empty() {
+            continue;
+        }
+        let (_, count, _, _) = section.shape();
+        if row >= count {
+            return Err(P8Error::TooManyLines {
+                section: section.name(),
+                line,
+            });
+        }
+        let values = parse_line(section, line, content)?;
+        let target = match section {
+            Section::Gfx => &mut gfx,
+            Section::Gff => &mut flags,
+            Section::Map => &mut map_rows,
+            _ => label.as_mut().expect("label section"),
+        };
+        target[(row * values.len())..((row + 1) * values.len())].copy_from_slice(&values);
+        row += 1;
+    }
+
+    let mut tiles = map_rows;
+    // each shared byte holds two pixels, the left one in the low nibble
+    let shared_start = P8_SHEET_SIZE * P8_SHEET_SIZE / 2;
+    for pair in gfx[shared_start..].chunks(2) {
+        tiles.push(pair[0] | (pair[1] << 4));
+    }
+    Ok(P8Cart {
+        gfx,
+        flags,
+        map: TileMap {
+            width: P8_MAP_WIDTH,
+            height: P8_MAP_HEIGHT,
+            tiles,
+        },
+        label,
+    })
+}
+
+impl P8Cart {
+    pub fn to_asset(&self) -> PicoAsset {
+        PicoAsset {
+            width: P8_SHEET_SIZE,
+            height: P8_SHEET_SIZE,
+            palette: Some(PICO8_EXTENDED.rgb()),
+            pixels: self.gfx.clone(),
+            sprite_flags: Some(self.flags.clone()),
+            map: Some(self.map.clone()),
+            metadata: vec![],
+        }
+    }
+}
+
+// Loads the spritesheet into buffer 3 along with the sprite flags, the map
+// and the 32 PICO-8 colors. The label, if there is one, goes in buffer 2.
+pub fn load_p8(bytes: &[u8]) -> Result<P8Cart, P8Error> {
+    let cart = parse_p8(bytes)?;
+    let asset = cart.to_asset();
+    blit_pixels(3, asset.width, asset.height, &asset.pixels).map_err(P8Error::Asset)?;
+    if let Some(label) = &cart.label {
+        blit_pixels(2, P8_SHEET_SIZE, P8_SHEET_SIZE, label).map_err(P8Error::Asset)?;
+    }
+    if let Some(palette) = &asset.palette {
+        set_palette(palette);
+    }
+    set_sprite_flags(&cart.flags);
+    *TILE_MAP.0.borrow_mut() = Some(cart.map.clone());
+    Ok(cart)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cart(sections: &[(&str, Vec<String>)]) -> Vec<u8> {
+        let mut text = String::from("pico-8 cartridge // http://www.pico-8.com\nversion 18\n");
+        for (name, lines) in sections {
+            text.push_str(&format!("__{}__\n", name));
+            for line in lines {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+        text.into_bytes()
+    }
+
+    fn rows(count: usize, row: &str) -> Vec<String> {
+        vec![row.to_string(); count]
+    }
+
+    #[test]
+    fn reads_gfx_flags_map_and_label() {
+        let mut gfx = rows(P8_SHEET_SIZE, &"0".repeat(P8_SHEET_SIZE));
+        gfx[0] = format!("1f{}", "0".repeat(P8_SHEET_SIZE - 2));
+        gfx[64] = format!("12{}", "0".repeat(P8_SHEET_SIZE - 2));
+        let gff = rows(2, &format!("80{}", "0".repeat(254)));
+        let map = rows(1, &format!("0a{}", "0".repeat(254)));
+        let label = rows(1, &format!("v{}", "0".repeat(P8_SHEET_SIZE - 1)));
+        let parsed = parse_p8(&cart(&[
+            ("gfx", gfx),
+            ("gff", gff),
+            ("map", map),
+            ("label", label),
+        ]))
+        .unwrap();
+        assert_eq!(&parsed.gfx[..2], &[1, 15]);
+        assert_eq!(parsed.flags[0], 0x80);
+        assert_eq!(parsed.flags[128], 0x80);
+        assert_eq!(parsed.map.tiles[0], 10);
+        // first shared row, two pixels to a byte
+        assert_eq!(parsed.map.tiles[P8_MAP_WIDTH * P8_MAP_TEXT_ROWS], 0x21);
+        assert_eq!(parsed.label.unwrap()[0], 31);
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        assert_eq!(parse_p8(&[0xff, 0xfe]), Err(P8Error::NotUtf8));
+    }
+
+    #[test]
+    fn rejects_a_missing_header() {
+        assert_eq!(parse_p8(b"__gfx__\n"), Err(P8Error::MissingHeader));
+    }
+
+    #[test]
+    fn rejects_too_many_lines() {
+        let gff = rows(3, &"0".repeat(256));
+        assert_eq!(
+            parse_p8(&cart(&[("gff", gff)])),
+            Err(P8Error::TooManyLines {
+                section: "gff",
+                line: 6
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_lines_of_the_wrong_length() {
+        assert_eq!(
+            parse_p8(&cart(&[("gfx", rows(1, "0123"))])),
+            Err(P8Error::WrongLength {
+                section: "gfx",
+                line: 4,
+                expected: P8_SHEET_SIZE,
+                found: 4
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_digits_outside_the_section_range() {
+        // v is only valid in the label, which has all 32 colors
+        let row = format!("v{}", "0".repeat(P8_SHEET_SIZE - 1));
+        assert_eq!(
+            parse_p8(&cart(&[("gfx", rows(1, &row))])),
+            Err(P8Error::InvalidDigit {
+                section: "gfx",
+                line: 4,
+                digit: 'v'
+            })
+        );
+    }
+
+    #[test]
+    fn asset_errors_keep_their_message() {
+        let err = || AssetError::TooLarge {
+            width: P8_SHEET_SIZE,
+            height: P8_SHEET_SIZE,
+            max_width: 64,
+            max_height: 64,
+        };
+        assert_eq!(P8Error::Asset(err()).to_string(), err().to_string());
+    }
+}
@@ -0,0 +1,336 @@
+use crate::asset::set_palette;
+use crate::pico::*;
+use std::fmt;
+use std::str;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PaletteFormat {
+    // Lospec .hex, one RRGGBB per line
+    Hex,
+    // GIMP .gpl
+    Gpl,
+    // JASC .pal as written by Paint Shop Pro and Aseprite
+    JascPal,
+    // Adobe .act, 256 raw rgb triples with an optional color count
+    Act,
+}
+
+impl PaletteFormat {
+    pub fn from_extension(extension: &str) -> Option<PaletteFormat> {
+        match extension.trim_start_matches('.').to_lowercase().as_str() {
+            "hex" => Some(PaletteFormat::Hex),
+            "gpl" => Some(PaletteFormat::Gpl),
+            "pal" => Some(PaletteFormat::JascPal),
+            "act" => Some(PaletteFormat::Act),
+            _ => None,
+        }
+    }
+
+    // Guesses the format from the contents, for files without an extension.
+    pub fn detect(bytes: &[u8]) -> PaletteFormat {
+        if bytes.starts_with(b"GIMP Palette") {
+            PaletteFormat::Gpl
+        } else if bytes.starts_with(b"JASC-PAL") {
+            PaletteFormat::JascPal
+        } else if bytes.len() == ACT_SIZE || bytes.len() == ACT_SIZE + 4 {
+            PaletteFormat::Act
+        } else {
+            PaletteFormat::Hex
+        }
+    }
+}
+
+const ACT_SIZE: usize = NUM_COLORS * 3;
+
+#[derive(Debug, PartialEq)]
+pub enum PaletteError {
+    NotUtf8,
+    MissingHeader(&'static str),
+    InvalidLine { line: usize, message: String },
+    WrongSize(usize),
+    TooManyColors(usize),
+    Empty,
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PaletteError::NotUtf8 => write!(f, "palette file is not valid utf-8"),
+            PaletteError::MissingHeader(header) => {
+                write!(f, "palette file does not start with {}", header)
+            }
+            PaletteError::InvalidLine { line, message } => write!(f, "line {}: {}", line, message),
+            PaletteError::WrongSize(size) => write!(
+                f,
+                "act palettes are {} or {} bytes, found {}",
+                ACT_SIZE,
+                ACT_SIZE + 4,
+                size
+            ),
+            PaletteError::TooManyColors(count) => write!(
+                f,
+                "palette has {} colors, at most {} are supported",
+                count, NUM_COLORS
+            ),
+            PaletteError::Empty => write!(f, "palette has no colors"),
+        }
+    }
+}
+
+fn invalid(line: usize, message: String) -> PaletteError {
+    PaletteError::InvalidLine { line, message }
+}
+
+fn parse_hex(text: &str) -> Result<Vec<u8>, PaletteError> {
+    let mut colors = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim().trim_start_matches('#');
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        if line.len() != 6 {
+            return Err(invalid(i + 1, format!("'{}' is not a RRGGBB color", line)));
+        }
+        let value = u32::from_str_radix(line, 16)
+            .map_err(|_| invalid(i + 1, format!("'{}' is not a RRGGBB color", line)))?;
+        colors.extend_from_slice(&[(value >> 16) as u8, (value >> 8) as u8, value as u8]);
+    }
+    Ok(colors)
+}
+
+fn parse_rgb_line(line: usize, content: &str) -> Result<[u8; 3], PaletteError> {
+    let values: Vec<&str> = content.split_whitespace().take(3).collect();
+    if values.len() < 3 {
+        return Err(invalid(line, format!("expected r g b, found '{}'", content)));
+    }
+    let mut rgb = [0; 3];
+    for (channel, value) in rgb.iter_mut().zip(values) {
+        *channel = value
+            .parse::<u8>()
+            .map_err(|_| invalid(line, format!("'{}' is not a value from 0 to 255", value)))?;
+    }
+    Ok(rgb)
+}
+
+fn parse_gpl(text: &str) -> Result<Vec<u8>, PaletteError> {
+    let mut lines = text.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == "GIMP Palette" => {}
+        _ => return Err(PaletteError::MissingHeader("GIMP Palette")),
+    }
+    let mut colors = vec![];
+    for (i, line) in lines {
+        let line = line.trim();
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("Name:")
+            || line.starts_with("Columns:")
+        {
+            continue;
+        }
+        colors.extend_from_slice(&parse_rgb_line(i + 1, line)?);
+    }
+    Ok(colors)
+}
+
+fn parse_jasc(text: &str) -> Result<Vec<u8>, PaletteError> {
+    let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l.trim()));
+    match lines.next() {
+        Some((_, "JASC-PAL")) => {}
+        _ => return Err(PaletteError::MissingHeader("JASC-PAL")),
+    }
+    // the version line, always 0100
+    lines.next();
+    let (count_line, count) = match lines.next() {
+        Some((line, count)) => (
+            line,
+            count
+                .parse::<usize>()
+                .map_err(|_| invalid(line, format!("'{}' is not a color count", count)))?,
+        ),
+        None => return Err(PaletteError::Empty),
+    };
+    let mut colors = vec![];
+    for (line, content) in lines.filter(|(_, l)| !l.is_empty()).take(count) {
+        colors.extend_from_slice(&parse_rgb_line(line, content)?);
+    }
+    if colors.len() < count * 3 {
+        return Err(invalid(
+            count_line,
+            format!("expected {} colors, found {}", count, colors.len() / 3),
+        ));
+    }
+    Ok(colors)
+}
+
+fn parse_act(bytes: &[u8]) -> Result<Vec<u8>, PaletteError> {
+    match bytes.len() {
+        ACT_SIZE => Ok(bytes.to_vec()),
+        // Photoshop appends a big endian color count and transparent index
+        size if size == ACT_SIZE + 4 => {
+            let count = ((bytes[ACT_SIZE] as usize) << 8 | bytes[ACT_SIZE + 1] as usize)
+                .max(1)
+                .min(NUM_COLORS);
+            Ok(bytes[..(count * 3)].to_vec())
+        }
+        size => Err(PaletteError::WrongSize(size)),
+    }
+}
+
+// Returns rgb triples, at most NUM_COLORS of them.
+pub fn parse_palette(bytes: &[u8], format: PaletteFormat) -> Result<Vec<u8>, PaletteError> {
+    let colors = match format {
+        PaletteFormat::Act => parse_act(bytes)?,
+        format => {
+            let text = str::from_utf8(bytes).map_err(|_| PaletteError::NotUtf8)?;
+            match format {
+                PaletteFormat::Hex => parse_hex(text)?,
+                PaletteFormat::Gpl => parse_gpl(text)?,
+                _ => parse_jasc(text)?,
+            }
+        }
+    };
+    if colors.is_empty() {
+        return Err(PaletteError::Empty);
+    }
+    if colors.len() > ACT_SIZE {
+        return Err(PaletteError::TooManyColors(colors.len() / 3));
+    }
+    Ok(colors)
+}
+
+pub fn write_palette(colors: &[u8], format: PaletteFormat) -> Vec<u8> {
+    let colors = &colors[..(colors.len() / 3 * 3).min(ACT_SIZE)];
+    let count = colors.len() / 3;
+    match format {
+        PaletteFormat::Hex => colors
+            .chunks(3)
+            .map(|c| format!("{:02x}{:02x}{:02x}\n", c[0], c[1], c[2]))
+            .collect::<String>()
+            .into_bytes(),
+        PaletteFormat::Gpl => {
+            let mut text = String::from("GIMP Palette\nName: pico\nColumns: 16\n#\n");
+            for c in colors.chunks(3) {
+                text.push_str(&format!(
+                    "{:3} {:3} {:3}\t{:02x}{:02x}{:02x}\n",
+                    c[0], c[1], c[2], c[0], c[1], c[2]
+                ));
+            }
+            text.into_bytes()
+        }
+        PaletteFormat::JascPal => {
+            let mut text = format!("JASC-PAL\r\n0100\r\n{}\r\n", count);
+            for c in colors.chunks(3) {
+                text.push_str(&format!("{} {} {}\r\n", c[0], c[1], c[2]));
+            }
+            text.into_bytes()
+        }
+        PaletteFormat::Act => {
+            let mut bytes = colors.to_vec();
+            bytes.resize(ACT_SIZE, 0);
+            // color count, and 0xFFFF for no transparent index
+            bytes.extend_from_slice(&[(count >> 8) as u8, count as u8, 0xFF, 0xFF]);
+            bytes
+        }
+    }
+}
+
+// Replaces the start of PALETTE, entries past the loaded colors are kept.
+pub fn load_palette(bytes: &[u8], format: PaletteFormat) -> Result<usize, PaletteError> {
+    let colors = parse_palette(bytes, format)?;
+    set_palette(&colors);
+    Ok(colors.len() / 3)
+}
+
+pub fn save_palette(format: PaletteFormat, count: usize) -> Vec<u8> {
+    let palette = PALETTE.0.borrow();
+    write_palette(&palette[..(count.min(NUM_COLORS) * 3)], format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMATS: [PaletteFormat; 4] = [
+        PaletteFormat::Hex,
+        PaletteFormat::Gpl,
+        PaletteFormat::JascPal,
+        PaletteFormat::Act,
+    ];
+
+    #[test]
+    fn every_format_round_trips() {
+        let five = vec![0, 0, 0, 255, 255, 255, 255, 0, 77, 1, 2, 3, 41, 173, 255];
+        let full: Vec<u8> = (0..ACT_SIZE).map(|i| (i * 7) as u8).collect();
+        for &format in FORMATS.iter() {
+            for colors in &[&five, &full] {
+                let written = write_palette(colors, format);
+                assert_eq!(PaletteFormat::detect(&written), format);
+                let parsed = parse_palette(&written, format).unwrap();
+                assert_eq!(&parsed, *colors, "{:?}", format);
+                assert_eq!(write_palette(&parsed, format), written, "{:?}", format);
+            }
+        }
+    }
+
+    #[test]
+    fn reads_files_from_other_tools() {
+        let colors = vec![0, 0, 0, 255, 0, 77];
+        let files: [(&[u8], PaletteFormat); 3] = [
+            (b"; paint.net\n#000000\n#FF004D\n", PaletteFormat::Hex),
+            (
+                b"GIMP Palette\nName: two\n#\n  0   0   0 black\n255   0  77 red\n",
+                PaletteFormat::Gpl,
+            ),
+            (
+                b"JASC-PAL\n0100\n2\n0 0 0\n255 0 77\n",
+                PaletteFormat::JascPal,
+            ),
+        ];
+        for &(bytes, format) in files.iter() {
+            assert_eq!(parse_palette(bytes, format), Ok(colors.clone()));
+        }
+    }
+
+    #[test]
+    fn malformed_palettes_are_rejected() {
+        let cases: [(&[u8], PaletteFormat, PaletteError); 8] = [
+            (
+                b"ff00\n",
+                PaletteFormat::Hex,
+                invalid(1, "'ff00' is not a RRGGBB color".to_owned()),
+            ),
+            (
+                b"00gg00\n",
+                PaletteFormat::Hex,
+                invalid(1, "'00gg00' is not a RRGGBB color".to_owned()),
+            ),
+            (b"\n; nothing\n", PaletteFormat::Hex, PaletteError::Empty),
+            (&[0xff, 0xfe], PaletteFormat::Hex, PaletteError::NotUtf8),
+            (
+                b"0 0 0\n",
+                PaletteFormat::Gpl,
+                PaletteError::MissingHeader("GIMP Palette"),
+            ),
+            (
+                b"GIMP Palette\n0 0 256\n",
+                PaletteFormat::Gpl,
+                invalid(2, "'256' is not a value from 0 to 255".to_owned()),
+            ),
+            (
+                b"JASC-PAL\n0100\n3\n0 0 0\n1 1 1\n",
+                PaletteFormat::JascPal,
+                invalid(3, "expected 3 colors, found 2".to_owned()),
+            ),
+            (&[0; 30], PaletteFormat::Act, PaletteError::WrongSize(30)),
+        ];
+        for (bytes, format, error) in cases.iter() {
+            assert_eq!(parse_palette(bytes, *format).as_ref(), Err(error));
+        }
+        let too_many: String = (0..(NUM_COLORS + 1)).map(|_| "000000\n").collect();
+        assert_eq!(
+            parse_palette(too_many.as_bytes(), PaletteFormat::Hex),
+            Err(PaletteError::TooManyColors(NUM_COLORS + 1))
+        );
+    }
+}
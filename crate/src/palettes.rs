@@ -0,0 +1,198 @@
+use crate::pico::*;
+
+pub struct NamedPalette {
+    pub name: &'static str,
+    pub colors: &'static [u32],
+}
+
+impl NamedPalette {
+    pub fn rgb(&self) -> Vec<u8> {
+        self.colors
+            .iter()
+            .flat_map(|c| vec![(c >> 16) as u8, (c >> 8) as u8, *c as u8])
+            .collect()
+    }
+}
+
+pub const PICO8_COLORS: [u32; 16] = [
+    0x000000, 0x1d2b53, 0x7e2553, 0x008751, 0xab5236, 0x5f574f, 0xc2c3c7, 0xfff1e8, 0xff004d,
+    0xffa300, 0xffec27, 0x00e436, 0x29adff, 0x83769c, 0xff77a8, 0xffccaa,
+];
+
+// the undocumented extra colors
+const PICO8_EXTRA_COLORS: [u32; 16] = [
+    0x291814, 0x111d35, 0x422136, 0x125359, 0x742f29, 0x49333b, 0xa28879, 0xf3ef7d, 0xbe1250,
+    0xff6c24, 0xa8e72e, 0x00b543, 0x065ab5, 0x754665, 0xff6e59, 0xff9d81,
+];
+
+pub const PICO8_EXTENDED_COLORS: [u32; 32] = pico8_extended();
+
+const fn pico8_extended() -> [u32; 32] {
+    let mut colors = [0; 32];
+    let mut i = 0;
+    while i < 16 {
+        colors[i] = PICO8_COLORS[i];
+        colors[i + 16] = PICO8_EXTRA_COLORS[i];
+        i += 1;
+    }
+    colors
+}
+
+// PICO8_COLORS as rgb bytes, pico::DEFAULT_COLORS is this.
+pub const fn pico8_rgb() -> [u8; 16 * 3] {
+    let mut rgb = [0; 16 * 3];
+    let mut i = 0;
+    while i < 16 {
+        let color = PICO8_COLORS[i];
+        rgb[i * 3] = (color >> 16) as u8;
+        rgb[i * 3 + 1] = (color >> 8) as u8;
+        rgb[i * 3 + 2] = color as u8;
+        i += 1;
+    }
+    rgb
+}
+
+pub static PICO8: NamedPalette = NamedPalette {
+    name: "pico-8",
+    colors: &PICO8_COLORS,
+};
+
+pub static PICO8_EXTENDED: NamedPalette = NamedPalette {
+    name: "pico-8-extended",
+    colors: &PICO8_EXTENDED_COLORS,
+};
+
+pub static DB16: NamedPalette = NamedPalette {
+    name: "db16",
+    colors: &[
+        0x140c1c, 0x442434, 0x30346d, 0x4e4a4e, 0x854c30, 0x346524, 0xd04648, 0x757161, 0x597dce,
+        0xd27d2c, 0x8595a1, 0x6daa2c, 0xd2aa99, 0x6dc2ca, 0xdad45e, 0xdeeed6,
+    ],
+};
+
+pub static DB32: NamedPalette = NamedPalette {
+    name: "db32",
+    colors: &[
+        0x000000, 0x222034, 0x45283c, 0x663931, 0x8f563b, 0xdf7126, 0xd9a066, 0xeec39a, 0xfbf236,
+        0x99e550, 0x6abe30, 0x37946e, 0x4b692f, 0x524b24, 0x323c39, 0x3f3f74, 0x306082, 0x5b6ee1,
+        0x639bff, 0x5fcde4, 0xcbdbfc, 0xffffff, 0x9badb7, 0x847e87, 0x696a6a, 0x595652, 0x76428a,
+        0xac3232, 0xd95763, 0xd77bba, 0x8f974a, 0x8a6f30,
+    ],
+};
+
+pub static ENDESGA32: NamedPalette = NamedPalette {
+    name: "endesga-32",
+    colors: &[
+        0xbe4a2f, 0xd77643, 0xead4aa, 0xe4a672, 0xb86f50, 0x733e39, 0x3e2731, 0xa22633, 0xe43b44,
+        0xf77622, 0xfeae34, 0xfee761, 0x63c74d, 0x3e8948, 0x265c42, 0x193c3e, 0x124e89, 0x0099db,
+        0x2ce8f5, 0xffffff, 0xc0cbdc, 0x8b9bb4, 0x5a6988, 0x3a4466, 0x262b44, 0x181425, 0xff0044,
+        0x68386c, 0xb55088, 0xf6757a, 0xe8b796, 0xc28569,
+    ],
+};
+
+pub static AAP64: NamedPalette = NamedPalette {
+    name: "aap-64",
+    colors: &[
+        0x060608, 0x141013, 0x3b1725, 0x73172d, 0xb4202a, 0xdf3e23, 0xfa6a0a, 0xf9a31b, 0xffd541,
+        0xfffc40, 0xd6f264, 0x9cdb43, 0x59c135, 0x14a02e, 0x1a7a3e, 0x24523b, 0x122020, 0x143464,
+        0x285cc4, 0x249fde, 0x20d6c7, 0xa6fcdb, 0xffffff, 0xfef3c0, 0xfad6b8, 0xf5a097, 0xe86a73,
+        0xbc4a9b, 0x793a80, 0x403353, 0x242234, 0x221c1a, 0x322b28, 0x71413b, 0xbb7547, 0xdba463,
+        0xf4d29c, 0xdae0ea, 0xb3b9d1, 0x8b93af, 0x6d758d, 0x4a5462, 0x333941, 0x422433, 0x5b3138,
+        0x8e5252, 0xba756a, 0xe9b5a3, 0xe3e6ff, 0xb9bffb, 0x849be4, 0x588dbe, 0x477d85, 0x23674e,
+        0x328464, 0x5daf8d, 0x92dcba, 0xcdf7e2, 0xe4d2aa, 0xc7b08b, 0xa08662, 0x796755, 0x5a4e44,
+        0x423934,
+    ],
+};
+
+pub static GAME_BOY: NamedPalette = NamedPalette {
+    name: "game-boy",
+    colors: &[0x0f380f, 0x306230, 0x8bac0f, 0x9bbc0f],
+};
+
+pub static CGA: NamedPalette = NamedPalette {
+    name: "cga",
+    colors: &[
+        0x000000, 0x0000aa, 0x00aa00, 0x00aaaa, 0xaa0000, 0xaa00aa, 0xaa5500, 0xaaaaaa, 0x555555,
+        0x5555ff, 0x55ff55, 0x55ffff, 0xff5555, 0xff55ff, 0xffff55, 0xffffff,
+    ],
+};
+
+// Pepto's measured colors
+pub static C64: NamedPalette = NamedPalette {
+    name: "c64",
+    colors: &[
+        0x000000, 0xffffff, 0x68372b, 0x70a4b2, 0x6f3d86, 0x588d43, 0x352879, 0xb8c76f, 0x6f4f25,
+        0x433900, 0x9a6759, 0x444444, 0x6c6c6c, 0x9ad284, 0x6c5eb5, 0x959595,
+    ],
+};
+
+pub static PALETTES: &[&NamedPalette] = &[
+    &PICO8,
+    &PICO8_EXTENDED,
+    &DB16,
+    &DB32,
+    &ENDESGA32,
+    &AAP64,
+    &GAME_BOY,
+    &CGA,
+    &C64,
+];
+
+// Names are matched ignoring case, spaces, dashes and underscores, so
+// "PICO-8", "pico8" and "Game Boy" all work.
+pub fn find_palette(name: &str) -> Option<&'static NamedPalette> {
+    let normalize = |name: &str| -> String {
+        name.chars()
+            .filter(|c| !(c.is_whitespace() || *c == '-' || *c == '_'))
+            .flat_map(|c| c.to_lowercase())
+            .collect()
+    };
+    let wanted = normalize(name);
+    PALETTES
+        .iter()
+        .find(|palette| normalize(palette.name) == wanted)
+        .cloned()
+}
+
+// Copies a palette into PALETTE starting at offset, returns how many
+// colors fit.
+pub fn apply_palette(palette: &NamedPalette, offset: usize) -> usize {
+    if offset >= NUM_COLORS {
+        return 0;
+    }
+    let rgb = palette.rgb();
+    let len = rgb.len().min((NUM_COLORS - offset) * 3);
+    PALETTE.0.borrow_mut()[(offset * 3)..(offset * 3 + len)].copy_from_slice(&rgb[..len]);
+    len / 3
+}
+
+const CUBE_LEVELS: [u8; 6] = [0, 51, 102, 153, 204, 255];
+
+// A 6x6x6 color cube followed by a gray ramp, the same layout as the
+// xterm 256 color palette.
+pub fn ramp_colors() -> Vec<u8> {
+    let mut colors = vec![];
+    for r in CUBE_LEVELS.iter() {
+        for g in CUBE_LEVELS.iter() {
+            for b in CUBE_LEVELS.iter() {
+                colors.extend_from_slice(&[*r, *g, *b]);
+            }
+        }
+    }
+    // skips black and white, the cube already has them
+    for i in 0..24 {
+        let gray = (8 + i * 10) as u8;
+        colors.extend_from_slice(&[gray, gray, gray]);
+    }
+    colors
+}
+
+// Fills PALETTE from first onwards with ramp_colors, as many as fit.
+pub fn fill_unused(first: usize) {
+    if first >= NUM_COLORS {
+        return;
+    }
+    let ramps = ramp_colors();
+    let len = ((NUM_COLORS - first) * 3).min(ramps.len());
+    PALETTE.0.borrow_mut()[(first * 3)..(first * 3 + len)].copy_from_slice(&ramps[..len]);
+}
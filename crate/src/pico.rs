@@ -1,4 +1,6 @@
 use crate::font::*;
+use crate::markup::*;
+use crate::palettes::pico8_rgb;
 use euclid::{Point2D, Vector2D};
 use std::cell::RefCell;
 use std::cmp;
@@ -38,11 +40,7 @@ pub const MAX_HEIGHT: usize = 1024;
 
 pub const MAX_SCREEN_SIZE: usize = MAX_WIDTH * MAX_HEIGHT;
 pub const NUM_COLORS: usize = 256;
-pub const DEFAULT_COLORS: [u8; 16 * 3] = [
-    0, 0, 0, 29, 43, 83, 126, 37, 83, 0, 135, 81, 171, 82, 54, 95, 87, 79, 194, 195, 199, 255, 241,
-    232, 255, 0, 77, 255, 164, 0, 255, 236, 39, 0, 228, 54, 41, 173, 255, 131, 118, 156, 255, 119,
-    168, 255, 204, 170,
-];
+pub const DEFAULT_COLORS: [u8; 16 * 3] = pico8_rgb();
 
 #[derive(Copy, Clone, Debug)]
 pub struct Point {
@@ -68,6 +66,93 @@ pub enum MouseButtonState {
 
 pub const POINTER_COUNT: usize = 10;
 
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PointerType {
+    Mouse,
+    Pen,
+    Touch,
+    Unknown,
+}
+
+impl PointerType {
+    // The values of PointerEvent.pointerType
+    pub fn from_name(name: &str) -> PointerType {
+        match name {
+            "mouse" => PointerType::Mouse,
+            "pen" => PointerType::Pen,
+            "touch" => PointerType::Touch,
+            _ => PointerType::Unknown,
+        }
+    }
+}
+
+// Everything a PointerEvent knows about a contact besides its position and
+// buttons. Tilts and twist are in degrees, width and height in screen pixels.
+#[derive(Copy, Clone, Debug)]
+pub struct PointerInfo {
+    pub pressure: f32,
+    pub tilt_x: f32,
+    pub tilt_y: f32,
+    pub twist: f32,
+    pub width: f32,
+    pub height: f32,
+    pub pointer_type: PointerType,
+}
+
+pub const DEFAULT_POINTER_INFO: PointerInfo = PointerInfo {
+    pressure: 0.0,
+    tilt_x: 0.0,
+    tilt_y: 0.0,
+    twist: 0.0,
+    width: 1.0,
+    height: 1.0,
+    pointer_type: PointerType::Unknown,
+};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PointerEventKind {
+    // a pointer id was given a slot
+    Begin,
+    Move,
+    Down,
+    Up,
+    // the pointer left the canvas or was cancelled, its slot is free again
+    End,
+}
+
+impl PointerEventKind {
+    pub fn from_code(code: u8) -> Option<PointerEventKind> {
+        match code {
+            0 => Some(PointerEventKind::Move),
+            1 => Some(PointerEventKind::Down),
+            2 => Some(PointerEventKind::Up),
+            _ => None,
+        }
+    }
+}
+
+// One input event as it happened, time is the host's event timestamp in
+// milliseconds.
+#[derive(Copy, Clone, Debug)]
+pub struct PointerEvent {
+    pub kind: PointerEventKind,
+    // the slot, and the host's id for the pointer in it
+    pub pointer: usize,
+    pub id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub buttons: u32,
+    pub pressure: f32,
+    pub time: f64,
+}
+
+impl PointerEvent {
+    pub fn btn(&self, btn_num: u8) -> bool {
+        (btn_num as usize) < BUTTON_VALUES.len()
+            && (self.buttons & BUTTON_VALUES[btn_num as usize]) > 0
+    }
+}
+
 pub struct State {
     pub time: f32,
     pub offset: Point,
@@ -78,14 +163,27 @@ pub struct State {
     pub clip_rect: ClipRect,
     pub transparency: [bool; NUM_COLORS],
     pub mouse_buttons: [MouseButtonState; 5],
+    // vertical, the same as scroll_y
     pub scroll: f64,
     pub scroll_delta: f64,
+    pub scroll_x: f64,
+    pub scroll_delta_x: f64,
+    pub scroll_delta_z: f64,
+    // ctrl + wheel, which is what trackpad pinches arrive as
+    pub zoom: f64,
+    pub zoom_delta: f64,
     pub pointer_pos: [Option<Point>; POINTER_COUNT],
     pub last_pointer_pos: [Option<Point>; POINTER_COUNT],
     pub pointer_state: [u32; POINTER_COUNT],
     pub last_pointer_state: [u32; POINTER_COUNT],
+    pub pointer_info: [PointerInfo; POINTER_COUNT],
+    // the host's pointer id that owns each slot
+    pub pointer_ids: [Option<i32>; POINTER_COUNT],
+    // everything since the last update, cleared after each update
+    pub pointer_events: Vec<PointerEvent>,
     pub pointer_pos_changed: bool,
     pub pointer_state_changed: bool,
+    pub dirty_rect: Option<ClipRect>,
 }
 
 /*
@@ -130,12 +228,21 @@ pub static STATE: Container = Container(RefCell::new(State {
     mouse_buttons: [MouseButtonState::Up; 5],
     scroll: 0.0,
     scroll_delta: 0.0,
+    scroll_x: 0.0,
+    scroll_delta_x: 0.0,
+    scroll_delta_z: 0.0,
+    zoom: 0.0,
+    zoom_delta: 0.0,
     pointer_pos: [None; 10],
     last_pointer_pos: [None; 10],
     pointer_state: [0; 10],
     last_pointer_state: [0; 10],
+    pointer_info: [DEFAULT_POINTER_INFO; 10],
+    pointer_ids: [None; 10],
+    pointer_events: Vec::new(),
     pointer_pos_changed: false,
     pointer_state_changed: false,
+    dirty_rect: None,
 }));
 
 pub static SCREEN: Screen = Screen(RefCell::new([0; MAX_SCREEN_SIZE]));
@@ -176,6 +283,8 @@ where
     (x0, y0, x1, y1)
 }
 
+// Writing to target 0 through this doesn't mark anything dirty, call
+// mark_dirty or mark_screen_dirty after or the host won't upload it.
 pub fn screen<'a>(i: u8) -> std::cell::RefMut<'a, [u8; MAX_SCREEN_SIZE]> {
     match i {
         // Match a single value
@@ -230,12 +339,53 @@ pub fn set_dimensions(width: usize, height: usize) {
     if width * height > MAX_SCREEN_SIZE {
         panic!();
     }
+    {
+        let mut state = STATE.0.borrow_mut();
+        state.clip_rect.t = 0;
+        state.clip_rect.l = 0;
+        state.clip_rect.r = width as i32;
+        state.clip_rect.b = height as i32;
+        (*state).dimensions = (width, height);
+    }
+    mark_screen_dirty(0);
+}
+
+// Grows the dirty bounding box when a write lands on target 0 so the host can
+// upload only the changed part of the screen. r and b are exclusive.
+pub fn mark_dirty(target: u8, l: i32, t: i32, r: i32, b: i32) {
+    if target != 0 {
+        return;
+    }
     let mut state = STATE.0.borrow_mut();
-    state.clip_rect.t = 0;
-    state.clip_rect.l = 0;
-    state.clip_rect.r = width as i32;
-    state.clip_rect.b = height as i32;
-    (*state).dimensions = (width, height);
+    let (width, height) = state.dimensions;
+    let l = cmp::max(l, 0);
+    let t = cmp::max(t, 0);
+    let r = cmp::min(r, width as i32);
+    let b = cmp::min(b, height as i32);
+    if l >= r || t >= b {
+        return;
+    }
+    state.dirty_rect = Some(match state.dirty_rect {
+        Some(dirty) => ClipRect {
+            l: cmp::min(dirty.l, l),
+            t: cmp::min(dirty.t, t),
+            r: cmp::max(dirty.r, r),
+            b: cmp::max(dirty.b, b),
+        },
+        None => ClipRect { l, t, r, b },
+    });
+}
+
+pub fn mark_screen_dirty(target: u8) {
+    mark_dirty(target, 0, 0, WIDTH() as i32, HEIGHT() as i32);
+}
+
+pub fn get_dirty_rect() -> Option<ClipRect> {
+    STATE.0.borrow().dirty_rect
+}
+
+pub fn reset_dirty_rect() {
+    STATE.0.borrow_mut().dirty_rect = None;
 }
 
 pub fn offset_point(x: i32, y: i32) -> (i32, i32) {
@@ -282,6 +432,124 @@ pub fn get_last_pointer_position(pointer: usize) -> Option<Point> {
     STATE.0.borrow().last_pointer_pos[pointer]
 }
 
+pub fn get_pointer_info(pointer: usize) -> PointerInfo {
+    STATE
+        .0
+        .borrow()
+        .pointer_info
+        .get(pointer)
+        .cloned()
+        .unwrap_or(DEFAULT_POINTER_INFO)
+}
+// 0 to 1, mice report 0.5 while a button is down
+pub fn get_pointer_pressure(pointer: usize) -> f32 {
+    get_pointer_info(pointer).pressure
+}
+pub fn get_pointer_tilt(pointer: usize) -> (f32, f32) {
+    let info = get_pointer_info(pointer);
+    (info.tilt_x, info.tilt_y)
+}
+pub fn get_pointer_twist(pointer: usize) -> f32 {
+    get_pointer_info(pointer).twist
+}
+pub fn get_pointer_size(pointer: usize) -> (f32, f32) {
+    let info = get_pointer_info(pointer);
+    (info.width, info.height)
+}
+pub fn get_pointer_type(pointer: usize) -> PointerType {
+    get_pointer_info(pointer).pointer_type
+}
+
+pub fn get_pointer_id(pointer: usize) -> Option<i32> {
+    STATE.0.borrow().pointer_ids.get(pointer).cloned().unwrap_or(None)
+}
+pub fn get_pointer_slot(id: i32) -> Option<usize> {
+    STATE.0.borrow().pointer_ids.iter().position(|slot| *slot == Some(id))
+}
+pub fn get_pointer_position_by_id(id: i32) -> Option<Point> {
+    get_pointer_slot(id).and_then(get_pointer_position)
+}
+pub fn get_last_pointer_position_by_id(id: i32) -> Option<Point> {
+    get_pointer_slot(id).and_then(get_last_pointer_position)
+}
+pub fn get_pointer_info_by_id(id: i32) -> Option<PointerInfo> {
+    get_pointer_slot(id).map(get_pointer_info)
+}
+pub fn pointer_btn_by_id(id: i32, btn_num: u8) -> bool {
+    get_pointer_slot(id).map_or(false, |slot| pointer_btn(slot as u8, btn_num))
+}
+
+// Gives id the lowest free slot, or the one it already has. A pointer keeps
+// its slot until end_pointer, so lifting one finger never moves another.
+// The new slot has no last position, so deltas don't jump from whatever
+// used the slot before.
+pub fn begin_pointer(id: i32, x: i32, y: i32, time: f64) -> Option<usize> {
+    let slot = {
+        let mut state = STATE.0.borrow_mut();
+        let slot = match state.pointer_ids.iter().position(|slot| *slot == Some(id)) {
+            Some(slot) => slot,
+            None => state.pointer_ids.iter().position(|slot| slot.is_none())?,
+        };
+        state.pointer_ids[slot] = Some(id);
+        state.pointer_pos[slot] = Some(Point { x, y });
+        state.last_pointer_pos[slot] = None;
+        state.pointer_pos_changed = true;
+        slot
+    };
+    push_pointer_event(PointerEvent {
+        kind: PointerEventKind::Begin,
+        pointer: slot,
+        id,
+        x,
+        y,
+        buttons: 0,
+        pressure: 0.0,
+        time,
+    });
+    Some(slot)
+}
+
+pub fn end_pointer(id: i32, time: f64) -> Option<usize> {
+    let (slot, pos) = {
+        let mut state = STATE.0.borrow_mut();
+        let slot = state.pointer_ids.iter().position(|slot| *slot == Some(id))?;
+        let pos = state.pointer_pos[slot].unwrap_or(Point { x: 0, y: 0 });
+        state.pointer_ids[slot] = None;
+        state.pointer_pos[slot] = None;
+        state.pointer_state[slot] = 0;
+        state.pointer_info[slot] = DEFAULT_POINTER_INFO;
+        state.pointer_pos_changed = true;
+        state.pointer_state_changed = true;
+        (slot, pos)
+    };
+    push_pointer_event(PointerEvent {
+        kind: PointerEventKind::End,
+        pointer: slot,
+        id,
+        x: pos.x,
+        y: pos.y,
+        buttons: 0,
+        pressure: 0.0,
+        time,
+    });
+    Some(slot)
+}
+
+pub fn push_pointer_event(event: PointerEvent) {
+    STATE.0.borrow_mut().pointer_events.push(event);
+}
+// The events queued since the last update, oldest first. Anything not
+// taken is dropped at the end of the update.
+pub fn take_pointer_events() -> Vec<PointerEvent> {
+    std::mem::replace(&mut STATE.0.borrow_mut().pointer_events, vec![])
+}
+pub fn get_pointer_events() -> Vec<PointerEvent> {
+    STATE.0.borrow().pointer_events.clone()
+}
+pub fn clear_pointer_events() {
+    STATE.0.borrow_mut().pointer_events.clear();
+}
+
 pub fn has_any_pointer_position_changed() -> bool {
     STATE.0.borrow().pointer_pos_changed
 }
@@ -289,12 +557,77 @@ pub fn has_any_pointer_state_changed() -> bool {
     STATE.0.borrow().pointer_state_changed
 }
 
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum WheelMode {
+    Pixel,
+    Line,
+    Page,
+}
+
+impl WheelMode {
+    // WheelEvent.deltaMode
+    pub fn from_code(code: u32) -> WheelMode {
+        match code {
+            1 => WheelMode::Line,
+            2 => WheelMode::Page,
+            _ => WheelMode::Pixel,
+        }
+    }
+}
+
+// What a line of wheel scrolling is in pixels, a page is the screen height.
+pub const WHEEL_LINE_HEIGHT: f64 = 16.0;
+
+// Adds one wheel event, converted to pixels. With ctrl held the vertical
+// delta goes to the zoom channel instead, negated so pinching out zooms in.
+pub fn add_wheel(dx: f64, dy: f64, dz: f64, mode: WheelMode, ctrl: bool) {
+    let scale = match mode {
+        WheelMode::Pixel => 1.0,
+        WheelMode::Line => WHEEL_LINE_HEIGHT,
+        WheelMode::Page => HEIGHT() as f64,
+    };
+    let (dx, dy, dz) = (dx * scale, dy * scale, dz * scale);
+    let mut state = STATE.0.borrow_mut();
+    if ctrl {
+        state.zoom -= dy;
+        state.zoom_delta -= dy;
+        return;
+    }
+    state.scroll_x += dx;
+    state.scroll_delta_x += dx;
+    state.scroll += dy;
+    state.scroll_delta += dy;
+    state.scroll_delta_z += dz;
+}
+
+pub fn clear_wheel_deltas() {
+    let mut state = STATE.0.borrow_mut();
+    state.scroll_delta = 0.0;
+    state.scroll_delta_x = 0.0;
+    state.scroll_delta_z = 0.0;
+    state.zoom_delta = 0.0;
+}
+
+// Vertical total and this frame's change.
 pub fn get_scroll() -> (f64, f64) {
     let state = STATE.0.borrow();
     (state.scroll, state.scroll_delta)
 }
+// This frame's x, y and z scroll in pixels.
+pub fn get_scroll_delta() -> (f64, f64, f64) {
+    let state = STATE.0.borrow();
+    (state.scroll_delta_x, state.scroll_delta, state.scroll_delta_z)
+}
 pub fn has_scroll_changed() -> bool {
-    STATE.0.borrow().scroll_delta != 0.0
+    let state = STATE.0.borrow();
+    state.scroll_delta != 0.0 || state.scroll_delta_x != 0.0 || state.scroll_delta_z != 0.0
+}
+pub fn get_zoom() -> (f64, f64) {
+    let state = STATE.0.borrow();
+    (state.zoom, state.zoom_delta)
+}
+pub fn has_zoom_changed() -> bool {
+    STATE.0.borrow().zoom_delta != 0.0
 }
 
 pub fn get_mouse_btn(btn: u8) -> MouseButtonState {
@@ -388,14 +721,18 @@ pub fn get_last_active_pointer_count() -> usize {
         .count()
 }
 
+pub fn get_clip() -> ClipRect {
+    STATE.0.borrow().clip_rect
+}
+
 pub fn set_clip(x: i32, y: i32, w: i32, h: i32) {
+    let width = WIDTH() as i32;
+    let height = HEIGHT() as i32;
     let mut state = STATE.0.borrow_mut();
     state.clip_rect.l = x;
     state.clip_rect.t = y;
     state.clip_rect.r = x + w;
     state.clip_rect.b = y + h;
-    let width = WIDTH() as i32;
-    let height = HEIGHT() as i32;
     if state.clip_rect.l < 0 {
         state.clip_rect.l = 0;
     }
@@ -414,8 +751,9 @@ pub fn pset(x: i32, y: i32, c: i32) {
     let c = wrap_byte(c);
     let (x, y) = offset_point(x, y);
     if is_point_on_screen(x, y) {
-        let mut screen = screen(get_target());
-        screen[(y as usize) * WIDTH() + (x as usize)] = c;
+        let target = get_target();
+        screen(target)[(y as usize) * WIDTH() + (x as usize)] = c;
+        mark_dirty(target, x, y, x + 1, y + 1);
     }
 }
 
@@ -444,10 +782,11 @@ pub fn palt(c: u8, t: bool) {
 
 pub fn cls(c: i32) {
     let c = wrap_byte(c);
-    let mut screen = screen(get_target());
-    for i in &mut screen[..] {
+    let target = get_target();
+    for i in &mut screen(target)[..] {
         *i = c
     }
+    mark_screen_dirty(target);
 }
 
 pub fn camera_set(x: i32, y: i32) {
@@ -556,7 +895,8 @@ pub fn rect(x0: i32, y0: i32, x1: i32, y1: i32, c: i32) {
     let (x0, y0) = offset_point(x0, y0);
     let (x1, y1) = offset_point(x1, y1);
     let (x0, y0, x1, y1) = rect_swap(x0, y0, x1, y1);
-    let mut screen = screen(get_target());
+    let target = get_target();
+    let mut screen = screen(target);
     let is_x0_on_screen = is_x_on_screen(x0);
     let is_y0_on_screen = is_y_on_screen(y0);
     let is_x1_on_screen = is_x_on_screen(x1);
@@ -588,6 +928,7 @@ pub fn rect(x0: i32, y0: i32, x1: i32, y1: i32, c: i32) {
                 screen[y * WIDTH() + x1] = c;
             }
         }
+        mark_dirty(target, x0, y0, x1 + 1, y1 + 1);
     }
 }
 
@@ -601,18 +942,29 @@ pub fn rect_fill(x0: i32, y0: i32, x1: i32, y1: i32, c: i32) {
     let c = wrap_byte(c);
     let (x0, y0) = offset_point(x0, y0);
     let (x1, y1) = offset_point(x1, y1);
-    if is_point_on_screen(x0, y0) || is_point_on_screen(x1, y1) {
-        let (x0, y0) = limit_point(x0, y0);
-        let (x1, y1) = limit_point(x1, y1);
-        let (x0, y0, x1, y1) = rect_swap(x0, y0, x1, y1);
-        let mut screen = screen(get_target());
-        let width = WIDTH();
-        for y in (y0 as usize)..((y1 as usize) + 1) {
-            let start = y * width + (x0 as usize);
-            let end = y * width + (x1 as usize) + 1;
-            screen[start..end].fill(c);
+    let (x0, y0, x1, y1) = rect_swap(x0, y0, x1, y1);
+    // the clip rect is always inside the screen
+    let (width, clip_rect) = {
+        let state = STATE.0.borrow();
+        (state.dimensions.0, state.clip_rect)
+    };
+    let l = cmp::max(x0, clip_rect.l);
+    let t = cmp::max(y0, clip_rect.t);
+    let r = cmp::min(x1 + 1, clip_rect.r);
+    let b = cmp::min(y1 + 1, clip_rect.b);
+    if l >= r || t >= b {
+        return;
+    }
+    let target = get_target();
+    {
+        let mut screen = screen(target);
+        let (l, r) = (l as usize, r as usize);
+        for y in (t as usize)..(b as usize) {
+            let row = y * width;
+            screen[(row + l)..(row + r)].fill(c);
         }
     }
+    mark_dirty(target, l, t, r, b);
 }
 pub fn rect_fill_euclid(top_left: &Point2D<f64>, bottom_right: &Point2D<f64>, c: i32) {
     let top_left = top_left.to_i32();
@@ -678,18 +1030,45 @@ pub fn circ_fill(x: i32, y: i32, r: i32, c: i32) {
     }
     init_sides_buffer();
     circ_with_pixel_func(x, y, r, c, &set_side_pixel);
+    fill_side_spans(y - r, y + r + 1, 0, wrap_byte(c));
+}
 
+// Fills the rows [yt, yb) between the left and right edges collected in the
+// sides buffer, clipped to the clip rect. extend_right widens every span.
+fn fill_side_spans(yt: i32, yb: i32, extend_right: i32, c: u8) {
+    let target = get_target();
     let state = STATE.0.borrow();
-    let yt = cmp::max(state.clip_rect.t, y - r);
-    let height = HEIGHT() as i32;
-    let yb = cmp::min(state.clip_rect.b, y + r + 1);
-    let c = wrap_byte(c);
-    for _y in yt..yb {
-        let _y = _y as usize;
-        let xl = cmp::max(state.sides_buffer_left[_y], state.clip_rect.l) as usize;
-        let xr = cmp::min(state.sides_buffer_right[_y], state.clip_rect.r - 1) as usize;
-        hline(xl, xr, _y, c);
+    let width = state.dimensions.0;
+    let clip_rect = state.clip_rect;
+    let yt = cmp::max(cmp::max(clip_rect.t, 0), yt);
+    let yb = cmp::min(cmp::min(clip_rect.b, state.dimensions.1 as i32), yb);
+    let mut dirty = ClipRect {
+        l: width as i32,
+        t: yb,
+        r: 0,
+        b: yt,
+    };
+    {
+        let mut screen = screen(target);
+        for y in yt..yb {
+            let xl = cmp::max(cmp::max(state.sides_buffer_left[y as usize], clip_rect.l), 0);
+            let xr = cmp::min(
+                cmp::min(state.sides_buffer_right[y as usize] + extend_right, clip_rect.r - 1),
+                width as i32 - 1,
+            );
+            if xl > xr {
+                continue;
+            }
+            let row = (y as usize) * width;
+            screen[(row + xl as usize)..(row + xr as usize + 1)].fill(c);
+            dirty.l = cmp::min(dirty.l, xl);
+            dirty.r = cmp::max(dirty.r, xr + 1);
+            dirty.t = cmp::min(dirty.t, y);
+            dirty.b = cmp::max(dirty.b, y + 1);
+        }
     }
+    drop(state);
+    mark_dirty(target, dirty.l, dirty.t, dirty.r, dirty.b);
 }
 
 pub fn circ_fill_euclid(center: &Point2D<f64>, r: f64, c: i32) {
@@ -716,17 +1095,12 @@ pub fn tri_fill(x0: i32, y0: i32, x1: i32, y1: i32, x2: i32, y2: i32, c: i32) {
     line_with_pixel_func(x1, y1, x2, y2, c, &set_side_pixel);
     line_with_pixel_func(x2, y2, x0, y0, c, &set_side_pixel);
 
-    let c = wrap_byte(c);
-    let state = STATE.0.borrow();
-    let yt = cmp::max(state.clip_rect.t, cmp::min(y0, cmp::min(y1, y2)));
-    let yb = cmp::min(state.clip_rect.b, cmp::max(y0, cmp::max(y1, y2)) + 1);
-
-    for _y in yt..yb {
-        let _y = _y as usize;
-        let xl = cmp::max(state.sides_buffer_left[_y], state.clip_rect.l) as usize;
-        let xr = cmp::min(state.sides_buffer_right[_y] + 1, state.clip_rect.r - 1) as usize;
-        hline(xl, xr, _y, c);
-    }
+    fill_side_spans(
+        cmp::min(y0, cmp::min(y1, y2)),
+        cmp::max(y0, cmp::max(y1, y2)) + 1,
+        1,
+        wrap_byte(c),
+    );
 }
 
 pub fn tri_fill_euclid(v0: &Point2D<f64>, v1: &Point2D<f64>, v2: &Point2D<f64>, c: i32) {
@@ -740,43 +1114,86 @@ pub fn hline(x0: usize, x1: usize, y: usize, c: u8) {
     let width = WIDTH();
     let start = y * width + (x0 as usize);
     let end = y * width + (x1 as usize) + 1;
-    screen(get_target())[start..end].fill(c);
+    let target = get_target();
+    screen(target)[start..end].fill(c);
+    mark_dirty(target, x0 as i32, y as i32, x1 as i32 + 1, y as i32 + 1);
 }
 
 pub fn copy_screen(source: u8, target: u8) {
     let size = WIDTH() * HEIGHT();
     screen(target)[0..size].clone_from_slice(&screen(source)[0..size]);
+    mark_screen_dirty(target);
 }
 
 pub fn copy_screen_with_transparency(source: u8, target: u8) {
+    mark_screen_dirty(target);
     let size = WIDTH() * HEIGHT();
     let mut target_screen = screen(target);
     let source_screen = screen(source);
-    let transparency = &(STATE.0.borrow().transparency);
-    for i in 0..size {
-        let source_color = source_screen[i];
-        if !transparency[source_color as usize] {
-            target_screen[i] = source_color;
+    let transparency = STATE.0.borrow().transparency;
+    for (target_pixel, source_pixel) in target_screen[0..size]
+        .iter_mut()
+        .zip(source_screen[0..size].iter())
+    {
+        if !transparency[*source_pixel as usize] {
+            *target_pixel = *source_pixel;
         }
     }
 }
 
 pub fn copy_screen_with_transparency_mask(source: u8, target: u8, mask: u8) {
+    mark_screen_dirty(target);
     let size = WIDTH() * HEIGHT();
     let mut target_screen = screen(target);
     let source_screen = screen(source);
     let mask_screen = screen(mask);
-    let transparency = &(STATE.0.borrow().transparency);
-    for i in 0..size {
-        let source_color = source_screen[i];
-        let mask_color = mask_screen[i];
-        if transparency[mask_color as usize] {
-            target_screen[i] = source_color;
+    let transparency = STATE.0.borrow().transparency;
+    for ((target_pixel, source_pixel), mask_pixel) in target_screen[0..size]
+        .iter_mut()
+        .zip(source_screen[0..size].iter())
+        .zip(mask_screen[0..size].iter())
+    {
+        if transparency[*mask_pixel as usize] {
+            *target_pixel = *source_pixel;
         }
     }
 }
 
-// TODO: can be optomized to use clone_from_slice
+// Clips a width x height blit against the screen for both the source and
+// target positions. Returns the source x/y, target x/y and span size.
+fn clip_blit(
+    source_x: i32,
+    source_y: i32,
+    target_x: i32,
+    target_y: i32,
+    width: usize,
+    height: usize,
+) -> Option<(usize, usize, usize, usize, usize, usize)> {
+    let (screen_width, screen_height) = STATE.0.borrow().dimensions;
+    let (screen_width, screen_height) = (screen_width as i32, screen_height as i32);
+    let x0 = cmp::max(0, cmp::max(-source_x, -target_x));
+    let y0 = cmp::max(0, cmp::max(-source_y, -target_y));
+    let x1 = cmp::min(
+        width as i32,
+        cmp::min(screen_width - source_x, screen_width - target_x),
+    );
+    let y1 = cmp::min(
+        height as i32,
+        cmp::min(screen_height - source_y, screen_height - target_y),
+    );
+    if x0 >= x1 || y0 >= y1 {
+        return None;
+    }
+    Some((
+        (source_x + x0) as usize,
+        (source_y + y0) as usize,
+        (target_x + x0) as usize,
+        (target_y + y0) as usize,
+        (x1 - x0) as usize,
+        (y1 - y0) as usize,
+    ))
+}
+
 pub fn copy_sprite(
     source: u8,
     target: u8,
@@ -787,52 +1204,36 @@ pub fn copy_sprite(
     width: usize,
     height: usize,
 ) {
-    let mut target_screen = screen(target);
-    let source_screen = screen(source);
+    let (sx, sy, tx, ty, w, h) =
+        match clip_blit(source_x, source_y, target_x, target_y, width, height) {
+            Some(span) => span,
+            None => return,
+        };
     let screen_width = WIDTH();
-    let screen_height = HEIGHT();
-    if screen_width == 0 || screen_height == 0 {
-        return;
-    }
-
-    let source_clip_rect = ClipRect {
-        l: cmp::max(0, source_x),
-        r: cmp::min(screen_width as i32, source_x + width as i32),
-        t: cmp::max(0, source_y),
-        b: cmp::min(screen_height as i32, source_y + height as i32),
-    };
-    let target_clip_rect = ClipRect {
-        l: cmp::max(0, target_x),
-        r: cmp::min(screen_width as i32, target_x + width as i32),
-        t: cmp::max(0, target_y),
-        b: cmp::min(screen_height as i32, target_y + height as i32),
-    };
-    for y in 0..height {
-        let source_pixel_y = source_y + y as i32;
-        let target_pixel_y = target_y + y as i32;
-        if source_pixel_y >= source_clip_rect.t
-            && source_pixel_y < source_clip_rect.b
-            && target_pixel_y >= target_clip_rect.t
-            && target_pixel_y < target_clip_rect.b
-        {
-            for x in 0..width {
-                let source_pixel_x = source_x + x as i32;
-                let target_pixel_x = target_x + x as i32;
-                if source_pixel_x >= source_clip_rect.l
-                    && source_pixel_x < source_clip_rect.r
-                    && target_pixel_x >= target_clip_rect.l
-                    && target_pixel_x < target_clip_rect.r
-                {
-                    let source_pixel_x = source_pixel_x as usize;
-                    let source_pixel_y = source_pixel_y as usize;
-                    let target_pixel_x = target_pixel_x as usize;
-                    let target_pixel_y = target_pixel_y as usize;
-                    target_screen[(target_pixel_y * screen_width + target_pixel_x) as usize] =
-                        source_screen[(source_pixel_y * screen_width + source_pixel_x) as usize];
-                }
-            }
+    if source == target {
+        let mut buf = screen(target);
+        // walk rows away from the overlap so no source row is overwritten
+        // before it's copied
+        let rows: Box<Iterator<Item = usize>> = if ty > sy {
+            Box::new((0..h).rev())
+        } else {
+            Box::new(0..h)
+        };
+        for y in rows {
+            let s = (sy + y) * screen_width + sx;
+            let t = (ty + y) * screen_width + tx;
+            buf.copy_within(s..(s + w), t);
+        }
+    } else {
+        let mut target_screen = screen(target);
+        let source_screen = screen(source);
+        for y in 0..h {
+            let s = (sy + y) * screen_width + sx;
+            let t = (ty + y) * screen_width + tx;
+            target_screen[t..(t + w)].copy_from_slice(&source_screen[s..(s + w)]);
         }
     }
+    mark_dirty(target, tx as i32, ty as i32, (tx + w) as i32, (ty + h) as i32);
 }
 
 pub fn copy_sprite_with_transparency(
@@ -845,80 +1246,48 @@ pub fn copy_sprite_with_transparency(
     width: usize,
     height: usize,
 ) {
-    let mut target_screen = screen(target);
-    let source_screen = screen(source);
-    let transparency = &(STATE.0.borrow().transparency);
+    let (sx, sy, tx, ty, w, h) =
+        match clip_blit(source_x, source_y, target_x, target_y, width, height) {
+            Some(span) => span,
+            None => return,
+        };
     let screen_width = WIDTH();
-    let screen_height = HEIGHT();
-    if screen_width == 0 || screen_height == 0 {
-        return;
-    }
-
-    let source_clip_rect = ClipRect {
-        l: cmp::max(0, source_x),
-        r: cmp::min(screen_width as i32, source_x + width as i32),
-        t: cmp::max(0, source_y),
-        b: cmp::min(screen_height as i32, source_y + height as i32),
-    };
-    let target_clip_rect = ClipRect {
-        l: cmp::max(0, target_x),
-        r: cmp::min(screen_width as i32, target_x + width as i32),
-        t: cmp::max(0, target_y),
-        b: cmp::min(screen_height as i32, target_y + height as i32),
-    };
-    for y in 0..height {
-        let source_pixel_y = source_y + y as i32;
-        let target_pixel_y = target_y + y as i32;
-        if source_pixel_y >= source_clip_rect.t
-            && source_pixel_y < source_clip_rect.b
-            && target_pixel_y >= target_clip_rect.t
-            && target_pixel_y < target_clip_rect.b
-        {
-            for x in 0..width {
-                let source_pixel_x = source_x + x as i32;
-                let target_pixel_x = target_x + x as i32;
-                if source_pixel_x >= source_clip_rect.l
-                    && source_pixel_x < source_clip_rect.r
-                    && target_pixel_x >= target_clip_rect.l
-                    && target_pixel_x < target_clip_rect.r
-                {
-                    let source_pixel_x = source_pixel_x as usize;
-                    let source_pixel_y = source_pixel_y as usize;
-                    let target_pixel_x = target_pixel_x as usize;
-                    let target_pixel_y = target_pixel_y as usize;
-                    let source_color =
-                        source_screen[(source_pixel_y * screen_width + source_pixel_x) as usize];
-                    if !transparency[source_color as usize] {
-                        target_screen[(target_pixel_y * screen_width + target_pixel_x) as usize] =
-                            source_color
-                    }
+    let transparency = STATE.0.borrow().transparency;
+    if source == target {
+        let mut buf = screen(target);
+        let mut row = vec![0; w];
+        let rows: Box<Iterator<Item = usize>> = if ty > sy {
+            Box::new((0..h).rev())
+        } else {
+            Box::new(0..h)
+        };
+        for y in rows {
+            let s = (sy + y) * screen_width + sx;
+            let t = (ty + y) * screen_width + tx;
+            row.copy_from_slice(&buf[s..(s + w)]);
+            for (target_pixel, source_pixel) in buf[t..(t + w)].iter_mut().zip(row.iter()) {
+                if !transparency[*source_pixel as usize] {
+                    *target_pixel = *source_pixel;
+                }
+            }
+        }
+    } else {
+        let mut target_screen = screen(target);
+        let source_screen = screen(source);
+        for y in 0..h {
+            let s = (sy + y) * screen_width + sx;
+            let t = (ty + y) * screen_width + tx;
+            let source_row = &source_screen[s..(s + w)];
+            for (target_pixel, source_pixel) in
+                target_screen[t..(t + w)].iter_mut().zip(source_row.iter())
+            {
+                if !transparency[*source_pixel as usize] {
+                    *target_pixel = *source_pixel;
                 }
             }
         }
     }
-}
-
-fn as_u16_le(array: &[u8; 2]) -> u16 {
-    ((array[0] as u16) << 0) + ((array[1] as u16) << 8)
-}
-
-pub fn load_spritesheet(bytes: &[u8]) {
-    let width = as_u16_le(&[bytes[0], bytes[1]]) as usize;
-    let height = as_u16_le(&[bytes[2], bytes[3]]) as usize;
-    {
-        PALETTE
-            .0
-            .borrow_mut()
-            .clone_from_slice(&bytes[4..(NUM_COLORS * 3 + 4)]);
-    };
-
-    let offset = NUM_COLORS * 3 + 4;
-    let mut buf = BUFFER3.0.borrow_mut();
-    let buf_width = WIDTH();
-    for i in 0..height {
-        buf[(buf_width * i)..(buf_width * i + width)]
-            .copy_from_slice(&bytes[(offset + (width * i))..(offset + (width * (i + 1)))]);
-    }
+    mark_dirty(target, tx as i32, ty as i32, (tx + w) as i32, (ty + h) as i32);
 }
 
 // pub fn get_bit() {
@@ -927,38 +1296,115 @@ pub fn load_spritesheet(bytes: &[u8]) {
 //     return (bool)((arr[idx] >> bit) & 1);
 // }
 
-pub fn prnt(string: &str, x: i32, y: i32, w: i32, h: i32, c: i32) {
-    let mut _x = x;
-    let mut _y = y;
-    for character in string.chars() {
-        if character.is_ascii() {
-            let character = character as usize;
-            if character > 32 && character < 128 {
-                for i in 0..8 {
-                    let line = BIT_FONT[(((character - 33) * 8) + i) as usize];
-                    for j in 0..8 {
-                        if ((line >> j) & 1) == 1 {
-                            rect_fill(
-                                _x + j,
-                                _y + i as i32,
-                                w - 1 + _x + j,
-                                h - 1 + _y + i as i32,
-                                c,
-                            );
-                        }
+// Draws glyph with every pixel scaled up to w x h.
+pub fn draw_glyph(glyph: &Glyph, x: i32, y: i32, w: i32, h: i32, c: i32) {
+    for (i, line) in glyph.rows.iter().enumerate() {
+        for j in 0..glyph.width {
+            if ((line >> j) & 1) == 1 {
+                let px = x + (glyph.x_offset + j) * w;
+                let py = y + (glyph.y_offset + i as i32) * h;
+                rect_fill(px, py, w - 1 + px, h - 1 + py, c);
+            }
+        }
+    }
+}
+
+const OUTLINE_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+// Moves the characters of one line down so that characters drawn at smaller
+// sizes share a baseline with the largest, line_h being the largest h.
+fn sit_on_baseline(line: &mut [(char, i32, i32, TextStyle)], baseline: i32, line_h: i32) {
+    for (_, _, py, style) in line.iter_mut() {
+        *py += baseline * (line_h - style.h);
+    }
+}
+
+// Draws string with the markup described in markup.rs, w and h scale every
+// font pixel. Backgrounds go first, then shadows and outlines, then the text
+// itself so effects never cover a neighbouring character. Returns the cursor
+// position after the last character, like print_cursor did in the C++
+// version.
+pub fn prnt(string: &str, font: FontHandle, x: i32, y: i32, w: i32, h: i32, c: i32) -> Point {
+    let spans = parse_markup(string, &TextStyle::new(w, h, c));
+    with_font(font, |font| {
+        let mut placed: Vec<(char, i32, i32, TextStyle)> = vec![];
+        let mut _x = x;
+        let mut _y = y;
+        let mut previous = None;
+        // tallest scale on the current line, for the step to the next one
+        let mut line_h = 0;
+        let mut line_start = 0;
+        for span in &spans {
+            let style = span.style;
+            for character in span.text.chars() {
+                if character == '\r' {
+                    // carriage return, do nothing
+                    continue;
+                } else if character == '\n' {
+                    sit_on_baseline(&mut placed[line_start..], font.baseline, line_h);
+                    line_start = placed.len();
+                    _x = x;
+                    _y += font.line_height * cmp::max(line_h, style.h);
+                    line_h = 0;
+                    previous = None;
+                    continue;
+                } else if character == '\t' {
+                    let scale = cmp::max(style.w, 1);
+                    _x = x + font.next_tab_stop((_x - x) / scale) * scale;
+                    previous = None;
+                    continue;
+                }
+                if let Some(previous) = previous {
+                    _x += font.kerning(previous, character) * style.w;
+                }
+                placed.push((character, _x, _y, style));
+                _x += font.advance(character) * style.w;
+                line_h = cmp::max(line_h, style.h);
+                previous = Some(character);
+            }
+        }
+        sit_on_baseline(&mut placed[line_start..], font.baseline, line_h);
+
+        for (character, px, py, style) in &placed {
+            if let Some(background) = style.background {
+                rect_fill(
+                    *px,
+                    *py,
+                    px + font.advance(*character) * style.w - 1,
+                    py + font.line_height * style.h - 1,
+                    background,
+                );
+            }
+        }
+        for (character, px, py, style) in &placed {
+            if let Some(glyph) = font.glyph(*character) {
+                if let Some(shadow) = style.shadow {
+                    draw_glyph(glyph, px + style.w, py + style.h, style.w, style.h, shadow);
+                }
+                if let Some(outline) = style.outline {
+                    for (ox, oy) in OUTLINE_OFFSETS.iter() {
+                        let (ox, oy) = (px + ox * style.w, py + oy * style.h);
+                        draw_glyph(glyph, ox, oy, style.w, style.h, outline);
                     }
                 }
-                _x += 8;
-            } else if character == 13 {
-                // carriage return, do nothing
-            } else if character == 10 {
-                _x = x;
-                _y += 9;
-            } else {
-                _x += 8;
             }
         }
-    }
+        for (character, px, py, style) in &placed {
+            if let Some(glyph) = font.glyph(*character) {
+                draw_glyph(glyph, *px, *py, style.w, style.h, style.color);
+            }
+        }
+        Point { x: _x, y: _y }
+    })
     // for ( std::string::iterator it=str.begin(); it!=str.end(); ++it) {
     //     uint8_t character = (uint8_t) *it;
     //     if (character > 32 && character < 128) {
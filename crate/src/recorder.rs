@@ -0,0 +1,211 @@
+use crate::pico::*;
+use std::borrow::Cow;
+use std::cell::RefCell;
+
+struct RecordedFrame {
+    pixels: Vec<u8>,
+    // PALETTE resolved through PALETTE_SWAP when the frame was captured
+    palette: Vec<u8>,
+    // milliseconds since the previous frame
+    delta: f32,
+}
+
+pub struct Recording {
+    width: usize,
+    height: usize,
+    max_frames: usize,
+    frames: Vec<RecordedFrame>,
+}
+
+pub struct Recorder(pub RefCell<Option<Recording>>);
+unsafe impl Sync for Recorder {}
+
+pub static RECORDER: Recorder = Recorder(RefCell::new(None));
+
+// A max_frames of 0 records until stop_recording is called.
+pub fn start_recording(max_frames: usize) {
+    *RECORDER.0.borrow_mut() = Some(Recording {
+        width: WIDTH(),
+        height: HEIGHT(),
+        max_frames,
+        frames: vec![],
+    });
+}
+
+pub fn is_recording() -> bool {
+    RECORDER.0.borrow().is_some()
+}
+
+pub fn recorded_frames() -> usize {
+    RECORDER.0.borrow().as_ref().map_or(0, |r| r.frames.len())
+}
+
+fn resolved_palette() -> Vec<u8> {
+    let palette = PALETTE.0.borrow();
+    let swap = PALETTE_SWAP.0.borrow();
+    let mut resolved = Vec::with_capacity(NUM_COLORS * 3);
+    for i in 0..NUM_COLORS {
+        let color = swap[i] as usize * 3;
+        resolved.extend_from_slice(&palette[color..(color + 3)]);
+    }
+    resolved
+}
+
+// Called after each update, copies SCREEN into the recording.
+pub fn capture_frame(delta: f32) {
+    let mut recorder = RECORDER.0.borrow_mut();
+    let recording = match recorder.as_mut() {
+        Some(recording) => recording,
+        None => return,
+    };
+    if recording.max_frames > 0 && recording.frames.len() >= recording.max_frames {
+        return;
+    }
+    // a resize mid recording would scramble every following frame
+    if WIDTH() != recording.width || HEIGHT() != recording.height {
+        return;
+    }
+    let size = recording.width * recording.height;
+    recording.frames.push(RecordedFrame {
+        pixels: SCREEN.0.borrow()[..size].to_vec(),
+        palette: resolved_palette(),
+        delta,
+    });
+}
+
+struct ChangedRect {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+}
+
+fn changed_rect(previous: &[u8], current: &[u8], width: usize) -> Option<ChangedRect> {
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+    for (i, (a, b)) in previous.iter().zip(current.iter()).enumerate() {
+        if a != b {
+            let (x, y) = (i % width, i / width);
+            bounds = Some(match bounds {
+                Some((l, t, r, b)) => (l.min(x), t.min(y), r.max(x), b.max(y)),
+                None => (x, y, x, y),
+            });
+        }
+    }
+    bounds.map(|(l, t, r, b)| ChangedRect {
+        left: l,
+        top: t,
+        width: r - l + 1,
+        height: b - t + 1,
+    })
+}
+
+fn crop(pixels: &[u8], width: usize, rect: &ChangedRect) -> Vec<u8> {
+    let mut cropped = Vec::with_capacity(rect.width * rect.height);
+    for y in rect.top..(rect.top + rect.height) {
+        let start = y * width + rect.left;
+        cropped.extend_from_slice(&pixels[start..(start + rect.width)]);
+    }
+    cropped
+}
+
+// Pixels that didn't change become a transparent index, which compresses
+// much better, as long as some index is unused by the changed pixels.
+fn mask_unchanged(previous: &[u8], current: &mut [u8]) -> Option<u8> {
+    let mut used = [false; NUM_COLORS];
+    for (a, b) in previous.iter().zip(current.iter()) {
+        if a != b {
+            used[*b as usize] = true;
+        }
+    }
+    let transparent = used.iter().position(|used| !used)? as u8;
+    for (a, b) in previous.iter().zip(current.iter_mut()) {
+        if *a == *b {
+            *b = transparent;
+        }
+    }
+    Some(transparent)
+}
+
+fn delay(ms: f32) -> u16 {
+    (ms / 10.0).round().max(1.0).min(u16::max_value() as f32) as u16
+}
+
+pub fn encode_gif(recording: &Recording) -> Result<Vec<u8>, gif::EncodingError> {
+    let mut bytes = vec![];
+    let first = match recording.frames.first() {
+        Some(first) => first,
+        None => return Ok(bytes),
+    };
+    let (width, height) = (recording.width, recording.height);
+    // each encoded frame with how long it stays on screen, frames identical
+    // to the one before only lengthen that time
+    let mut frames: Vec<(gif::Frame, f32)> = vec![];
+    let mut previous: Option<&RecordedFrame> = None;
+    for frame in recording.frames.iter() {
+        if let Some((_, ms)) = frames.last_mut() {
+            *ms += frame.delta;
+        }
+        let palette = if frame.palette == first.palette {
+            None
+        } else {
+            Some(frame.palette.clone())
+        };
+        let full_frame = gif::Frame {
+            width: width as u16,
+            height: height as u16,
+            buffer: Cow::Owned(frame.pixels.clone()),
+            palette: palette.clone(),
+            ..gif::Frame::default()
+        };
+        let encoded = match previous {
+            None => full_frame,
+            Some(last) if last.palette != frame.palette => full_frame,
+            Some(last) => match changed_rect(&last.pixels, &frame.pixels, width) {
+                None => {
+                    previous = Some(frame);
+                    continue;
+                }
+                Some(rect) => {
+                    let before = crop(&last.pixels, width, &rect);
+                    let mut pixels = crop(&frame.pixels, width, &rect);
+                    let transparent = mask_unchanged(&before, &mut pixels);
+                    gif::Frame {
+                        left: rect.left as u16,
+                        top: rect.top as u16,
+                        width: rect.width as u16,
+                        height: rect.height as u16,
+                        buffer: Cow::Owned(pixels),
+                        transparent,
+                        palette,
+                        dispose: gif::DisposalMethod::Keep,
+                        ..gif::Frame::default()
+                    }
+                }
+            },
+        };
+        frames.push((encoded, 0.0));
+        previous = Some(frame);
+    }
+    {
+        let mut encoder =
+            gif::Encoder::new(&mut bytes, width as u16, height as u16, &first.palette)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        // nothing follows the last frame, hold it for one more frame time
+        let last_delta = recording.frames.last().map_or(0.0, |f| f.delta);
+        if let Some((_, ms)) = frames.last_mut() {
+            *ms += last_delta;
+        }
+        for (mut frame, ms) in frames {
+            frame.delay = delay(ms);
+            encoder.write_frame(&frame)?;
+        }
+    }
+    Ok(bytes)
+}
+
+pub fn stop_recording() -> Result<Vec<u8>, gif::EncodingError> {
+    match RECORDER.0.borrow_mut().take() {
+        Some(recording) => encode_gif(&recording),
+        None => Ok(vec![]),
+    }
+}
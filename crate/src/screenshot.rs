@@ -0,0 +1,73 @@
+use crate::asset::PicoAsset;
+use crate::pico::*;
+
+// The same lookup the shader does: screen index -> PALETTE_SWAP -> PALETTE.
+pub fn resolve_rgb(target: u8) -> Vec<u8> {
+    let (width, height) = (WIDTH(), HEIGHT());
+    let palette = PALETTE.0.borrow();
+    let swap = PALETTE_SWAP.0.borrow();
+    let pixels = screen(target);
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for index in pixels[..(width * height)].iter() {
+        let color = swap[*index as usize] as usize * 3;
+        rgb.extend_from_slice(&palette[color..(color + 3)]);
+    }
+    rgb
+}
+
+// Nearest neighbour upscale so every pixel becomes a scale x scale block.
+pub fn upscale_rgb(rgb: &[u8], width: usize, height: usize, scale: usize) -> Vec<u8> {
+    if scale <= 1 {
+        return rgb.to_vec();
+    }
+    let row_len = width * scale * 3;
+    let mut scaled = Vec::with_capacity(row_len * height * scale);
+    for y in 0..height {
+        let start = scaled.len();
+        for pixel in rgb[(y * width * 3)..((y + 1) * width * 3)].chunks(3) {
+            for _ in 0..scale {
+                scaled.extend_from_slice(pixel);
+            }
+        }
+        let row = scaled[start..(start + row_len)].to_vec();
+        for _ in 1..scale {
+            scaled.extend_from_slice(&row);
+        }
+    }
+    scaled
+}
+
+pub fn encode_png(rgb: &[u8], width: usize, height: usize) -> Result<Vec<u8>, png::EncodingError> {
+    let mut bytes = vec![];
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+        encoder.set_color(png::ColorType::RGB);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(rgb)?;
+    }
+    Ok(bytes)
+}
+
+pub fn export_png(target: u8, scale: usize) -> Result<Vec<u8>, png::EncodingError> {
+    let scale = scale.max(1);
+    let (width, height) = (WIDTH(), HEIGHT());
+    let rgb = upscale_rgb(&resolve_rgb(target), width, height, scale);
+    encode_png(&rgb, width * scale, height * scale)
+}
+
+// An asset's pixels through its own palette, or the current one if it has
+// none. Indices past the palette come out black.
+pub fn asset_png(asset: &PicoAsset) -> Result<Vec<u8>, png::EncodingError> {
+    let current = PALETTE.0.borrow().to_vec();
+    let palette = asset.palette.as_ref().unwrap_or(&current);
+    let mut rgb = Vec::with_capacity(asset.pixels.len() * 3);
+    for index in asset.pixels.iter() {
+        let color = *index as usize * 3;
+        match palette.get(color..(color + 3)) {
+            Some(color) => rgb.extend_from_slice(color),
+            None => rgb.extend_from_slice(&[0, 0, 0]),
+        }
+    }
+    encode_png(&rgb, asset.width, asset.height)
+}
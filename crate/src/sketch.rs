@@ -1,3 +1,4 @@
+use crate::document::Document;
 use std::cell::RefCell;
 
 pub trait Sketch {
@@ -5,10 +6,37 @@ pub trait Sketch {
      where
           Self: Sized;
      fn update(&mut self, new_time: f32, old_time: f32);
+     // Sketches that can be saved return what's on their canvas.
+     fn save(&self) -> Option<Document> {
+          None
+     }
+     // Returns false when the sketch doesn't support loading or the
+     // document doesn't fit it.
+     fn load(&mut self, _document: &Document) -> bool {
+          false
+     }
 }
 pub struct SketchContainer(pub RefCell<Option<Box<RefCell<Sketch>>>>);
 unsafe impl Sync for SketchContainer {}
 
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SketchInput {
+     Pointer,
+     // needs two or more pointers at once
+     Multitouch,
+     Wheel,
+}
+
+impl SketchInput {
+     pub fn name(self) -> &'static str {
+          match self {
+               SketchInput::Pointer => "pointer",
+               SketchInput::Multitouch => "multitouch",
+               SketchInput::Wheel => "wheel",
+          }
+     }
+}
+
 pub struct SketchDescriptor {
      pub name: &'static str,
      pub constructor: &'static (Fn() -> Box<RefCell<Sketch>>),
@@ -16,5 +44,109 @@ pub struct SketchDescriptor {
      pub desktop: bool,
      pub public: bool,
      pub url: &'static str,
+     pub description: &'static str,
+     pub author: &'static str,
+     pub tags: &'static [&'static str],
+     // yyyy-mm-dd
+     pub date: Option<&'static str>,
+     // what the sketch passes to set_dimensions, None if it keeps the default
+     pub resolution: Option<(usize, usize)>,
+     pub inputs: &'static [SketchInput],
+     // .pico bytes shown in the directory
+     pub thumbnail: Option<&'static [u8]>,
 }
 unsafe impl Sync for SketchDescriptor {}
+
+fn json_string(s: &str) -> String {
+     let mut json = String::from("\"");
+     for c in s.chars() {
+          match c {
+               '"' => json.push_str("\\\""),
+               '\\' => json.push_str("\\\\"),
+               '\n' => json.push_str("\\n"),
+               c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+               c => json.push(c),
+          }
+     }
+     json.push('"');
+     json
+}
+
+fn json_list<T>(items: &[T], f: impl Fn(&T) -> String) -> String {
+     let items: Vec<String> = items.iter().map(f).collect();
+     format!("[{}]", items.join(","))
+}
+
+impl SketchDescriptor {
+     // Everything but the constructor and the thumbnail bytes, keyed the
+     // way the host names things.
+     pub fn to_json(&self, index: usize) -> String {
+          let resolution = match self.resolution {
+               Some((width, height)) => format!("{{\"width\":{},\"height\":{}}}", width, height),
+               None => "null".into(),
+          };
+          format!(
+               "{{\"index\":{},\"name\":{},\"url\":{},\"isMobile\":{},\"isDesktop\":{},\"isPublic\":{},\"description\":{},\"author\":{},\"tags\":{},\"date\":{},\"resolution\":{},\"inputs\":{},\"hasThumbnail\":{}}}",
+               index,
+               json_string(self.name),
+               json_string(self.url),
+               self.mobile,
+               self.desktop,
+               self.public,
+               json_string(self.description),
+               json_string(self.author),
+               json_list(self.tags, |tag| json_string(tag)),
+               self.date.map_or("null".into(), json_string),
+               resolution,
+               json_list(self.inputs, |input| json_string(input.name())),
+               self.thumbnail.is_some(),
+          )
+     }
+}
+
+#[cfg(test)]
+mod tests {
+     use super::*;
+     use crate::sketches::SKETCHES;
+
+     #[test]
+     fn json_strings_are_escaped() {
+          assert_eq!(json_string("plain"), "\"plain\"");
+          assert_eq!(
+               json_string("say \"hi\"\\\n\tbye\u{1}"),
+               "\"say \\\"hi\\\"\\\\\\n\\u0009bye\\u0001\""
+          );
+          // anything past the control characters is kept as it is
+          assert_eq!(json_string("café ✓"), "\"café ✓\"");
+     }
+
+     #[test]
+     fn descriptors_escape_every_string() {
+          let descriptor = SketchDescriptor {
+               name: "a \"quoted\" name",
+               constructor: SKETCHES[0].constructor,
+               mobile: true,
+               desktop: false,
+               public: true,
+               url: "back\\slash",
+               description: "two\nlines",
+               author: "tab\there",
+               tags: &["x\"y"],
+               date: Some("2020-01-02"),
+               resolution: Some((128, 64)),
+               inputs: &[SketchInput::Pointer, SketchInput::Wheel],
+               thumbnail: None,
+          };
+          assert_eq!(
+               descriptor.to_json(3),
+               concat!(
+                    "{\"index\":3,\"name\":\"a \\\"quoted\\\" name\",\"url\":\"back\\\\slash\",",
+                    "\"isMobile\":true,\"isDesktop\":false,\"isPublic\":true,",
+                    "\"description\":\"two\\nlines\",\"author\":\"tab\\u0009here\",",
+                    "\"tags\":[\"x\\\"y\"],\"date\":\"2020-01-02\",",
+                    "\"resolution\":{\"width\":128,\"height\":64},",
+                    "\"inputs\":[\"pointer\",\"wheel\"],\"hasThumbnail\":false}"
+               )
+          );
+     }
+}
@@ -48,4 +48,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "circle-rectangles",
+    description: "Nested rectangles following a point around a circle.",
+    author: "HelveticaScenario",
+    tags: &["generative", "animation"],
+    date: None,
+    resolution: None,
+    inputs: &[],
+    thumbnail: None,
 };
@@ -29,6 +29,7 @@ impl Sketch for Diagonals {
                 screen[i] = ((x + y + o as usize) % 16) as u8;
             }
         }
+        mark_screen_dirty(0);
     }
 }
 
@@ -43,4 +44,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "diagonals",
+    description: "Diagonal stripes of the whole palette scrolling across the screen.",
+    author: "HelveticaScenario",
+    tags: &["generative", "palette"],
+    date: None,
+    resolution: None,
+    inputs: &[],
+    thumbnail: None,
 };
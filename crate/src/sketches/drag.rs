@@ -1,3 +1,4 @@
+use crate::display_list::*;
 use crate::pico::*;
 use crate::sketch::*;
 use nalgebra::{Isometry2, Point2, Vector2};
@@ -10,7 +11,7 @@ use std::cmp;
 use std::rc::Rc;
 
 pub trait Drawable {
-    fn draw(&self);
+    fn draw(&self, list: &mut DisplayList);
 }
 
 pub trait Collidable {
@@ -83,13 +84,13 @@ impl Collidable for Circle {
 }
 
 impl Drawable for Circle {
-    fn draw(&self) {
+    fn draw(&self, list: &mut DisplayList) {
         if self.should_draw {
             let center_x = self.center.x.round() as i32;
             let center_y = self.center.y.round() as i32;
             let radius = self.radius.round() as i32;
 
-            circ_fill(center_x, center_y, radius, self.color);
+            list.circ_fill(center_x, center_y, radius, self.color);
         }
     }
 }
@@ -159,7 +160,7 @@ impl Collidable for Rectangle {
 }
 
 impl Drawable for Rectangle {
-    fn draw(&self) {
+    fn draw(&self, list: &mut DisplayList) {
         if !self.should_draw {
             return;
         }
@@ -175,7 +176,7 @@ impl Drawable for Rectangle {
             right.round() as i32,
             bottom.round() as i32,
         );
-        rect_fill(
+        list.rect_fill(
             left + center_x,
             top + center_y,
             right + center_x,
@@ -189,6 +190,7 @@ pub struct Drag {
     rectangle: Rectangle,
     circle: Circle,
     dragging: bool,
+    last_frame: DisplayList,
 }
 
 impl Sketch for Drag {
@@ -200,12 +202,14 @@ impl Sketch for Drag {
             rectangle: Rectangle::new(Vector2::new(256.0, 256.0), Vector2::new(30.0, 30.0), 12),
             circle: Circle::new(Vector2::identity(), 2.0, 7),
             dragging: true,
+            last_frame: DisplayList::new(),
         }
     }
     fn update(&mut self, new_time: f32, old_time: f32) {
-        cls(1);
+        let mut frame = DisplayList::new();
+        frame.cls(1);
         let t = ((new_time / 200.0).sin() * 10.0).round() as i32;
-        rect_fill(40, 40, 80 + (t), 80, 9);
+        frame.rect_fill(40, 40, 80 + (t), 80, 9);
 
         let mouse_pos = get_pointer_position(0);
         let last_mouse_pos = get_last_pointer_position(0);
@@ -234,8 +238,14 @@ impl Sketch for Drag {
             self.circle.should_draw = false;
         }
 
-        self.rectangle.draw();
-        self.circle.draw();
+        self.rectangle.draw(&mut frame);
+        self.circle.draw(&mut frame);
+        // the screen already shows an identical frame, skip drawing and uploading it
+        match frame.diff(&self.last_frame) {
+            DisplayListDiff::Unchanged => {}
+            _ => frame.replay(),
+        }
+        self.last_frame = frame;
 
         // self.rectangle.color = 12;
         // rot_rect(
@@ -261,4 +271,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "drag",
+    description: "Drag a rectangle around with the pointer.",
+    author: "HelveticaScenario",
+    tags: &["interactive", "collision"],
+    date: None,
+    resolution: Some((512, 512)),
+    inputs: &[SketchInput::Pointer],
+    thumbnail: None,
 };
@@ -1,3 +1,4 @@
+use crate::document::Document;
 use crate::pico::*;
 use crate::sketch::*;
 use std::cell::RefCell;
@@ -23,31 +24,53 @@ impl Sketch for Drawing {
     }
     fn update(&mut self, new_time: f32, old_time: f32) {
         set_target(1);
-        let mouse_pos = get_mouse_pos();
-        if let Some(Point { x: new_x, y: new_y }) = mouse_pos {
-            let c: u8 = self.count + 1;
-            let c = (c % 15) + 1;
-            if let Some(Point {
-                x: last_x,
-                y: last_y,
-            }) = self.last_mouse
-            {
-                self.count += 1;
-                self.count = self.count % 15;
-                line(last_x, last_y, new_x, new_y, c as i32);
-            } else {
-                pset(new_x, new_y, c as i32);
+        // replay every move since the last frame so fast strokes stay smooth
+        for event in take_pointer_events() {
+            if event.pointer != 0 {
+                continue;
             }
-            self.last_mouse = Some(Point { x: new_x, y: new_y });
-            set_target(0);
-            copy_screen(1, 0);
-            circ_fill(new_x, new_y, 5, 9);
-        } else {
-            if let Some(Point { x, y }) = self.last_mouse {
-                self.last_mouse = None;
+            match event.kind {
+                PointerEventKind::End => self.last_mouse = None,
+                _ => {
+                    let c: u8 = self.count + 1;
+                    let c = (c % 15) + 1;
+                    if let Some(Point {
+                        x: last_x,
+                        y: last_y,
+                    }) = self.last_mouse
+                    {
+                        self.count += 1;
+                        self.count = self.count % 15;
+                        line(last_x, last_y, event.x, event.y, c as i32);
+                    } else {
+                        pset(event.x, event.y, c as i32);
+                    }
+                    self.last_mouse = Some(Point {
+                        x: event.x,
+                        y: event.y,
+                    });
+                }
             }
-            copy_screen(1, 0);
         }
+        set_target(0);
+        copy_screen(1, 0);
+        if let Some(Point { x, y }) = get_mouse_pos() {
+            circ_fill(x, y, 5, 9);
+        }
+    }
+    // there are no strokes to keep, only the pixels in buffer 1
+    fn save(&self) -> Option<Document> {
+        Some(Document::from_target(1))
+    }
+    fn load(&mut self, document: &Document) -> bool {
+        if document.width != WIDTH() || document.height != HEIGHT() {
+            return false;
+        }
+        self.last_mouse = None;
+        let loaded = document.draw(1).is_ok();
+        set_target(0);
+        copy_screen(1, 0);
+        loaded
     }
 }
 
@@ -62,4 +85,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "drawing",
+    description: "Draw with the pointer in colors that cycle as you go. Saves and loads pixels only, so svg export is just the background.",
+    author: "HelveticaScenario",
+    tags: &["drawing", "interactive"],
+    date: None,
+    resolution: None,
+    inputs: &[SketchInput::Pointer],
+    thumbnail: None,
 };
@@ -151,4 +151,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "erase",
+    description: "Erase a cover to show the checkered pattern underneath.",
+    author: "HelveticaScenario",
+    tags: &["drawing", "interactive", "transparency"],
+    date: None,
+    resolution: Some((512, 512)),
+    inputs: &[SketchInput::Pointer],
+    thumbnail: None,
 };
@@ -173,4 +173,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "erase-2",
+    description: "Erase at 1024x1024 with color 16 as the transparent one.",
+    author: "HelveticaScenario",
+    tags: &["drawing", "interactive", "transparency"],
+    date: None,
+    resolution: Some((1024, 1024)),
+    inputs: &[SketchInput::Pointer],
+    thumbnail: None,
 };
@@ -1,3 +1,5 @@
+use crate::asset::*;
+use crate::font::*;
 use crate::pico::*;
 use crate::sketch::*;
 use std::cell::RefCell;
@@ -5,6 +7,9 @@ use std::cmp;
 use wasm_bindgen::prelude::*;
 use web_sys::console::log_1;
 
+// also the catalog thumbnail, so the file is only embedded once
+const FACE_PICO: &[u8] = include_bytes!("face.pico");
+
 pub struct Face {}
 fn as_u16_le(array: &[u8; 2]) -> u16 {
     ((array[0] as u16) << 0) + ((array[1] as u16) << 8)
@@ -13,13 +18,21 @@ fn as_u16_le(array: &[u8; 2]) -> u16 {
 impl Sketch for Face {
     fn new() -> Face {
         set_dimensions(64, 64);
-        load_spritesheet(include_bytes!("face.pico"));
+        let error = load_spritesheet(FACE_PICO).err();
 
         // // let height = as_u16_le(&bytes[2..4]);
         // log_1(&JsValue::from(format!("{:?} {:?} {:?} {:?} {:?}", spritesheet.len(), bytes.len(), width, height, palette.len())));
         cls(7);
-        copy_screen(3, 0);
-        prnt("Hello", 1, 1, 1, 1, 7);
+        match &error {
+            None => {
+                copy_screen(3, 0);
+                prnt("Hello", DEFAULT_FONT, 1, 1, 1, 1, 7);
+            }
+            Some(err) => {
+                cls(0);
+                prnt(&format!("face.pico: {}", err), DEFAULT_FONT, 1, 1, 1, 1, 8);
+            }
+        }
         Face {}
     }
     fn update(&mut self, new_time: f32, old_time: f32) {}
@@ -36,4 +49,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "face",
+    description: "A face loaded from a .pico spritesheet.",
+    author: "HelveticaScenario",
+    tags: &["assets", "sprites"],
+    date: None,
+    resolution: Some((64, 64)),
+    inputs: &[],
+    thumbnail: Some(FACE_PICO),
 };
@@ -42,4 +42,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "lines",
+    description: "Two hundred random lines a frame, piling up forever.",
+    author: "HelveticaScenario",
+    tags: &["generative", "lines"],
+    date: None,
+    resolution: Some((1024, 1024)),
+    inputs: &[],
+    thumbnail: None,
 };
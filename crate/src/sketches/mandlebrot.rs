@@ -1,3 +1,4 @@
+use crate::gesture::*;
 use crate::pico::*;
 use crate::sketch::*;
 use std::cell::RefCell;
@@ -21,6 +22,7 @@ pub struct Mandlebrot {
     width: f64,
     height: f64,
     offset: u8,
+    gestures: GestureRecognizer,
 }
 
 impl Sketch for Mandlebrot {
@@ -41,6 +43,7 @@ impl Sketch for Mandlebrot {
             width: width as f64,
             height: height as f64,
             offset: 0,
+            gestures: GestureRecognizer::new(GestureConfig::default()),
         };
         set_target(1);
         m.draw();
@@ -50,46 +53,60 @@ impl Sketch for Mandlebrot {
         let pointer_state_changed = has_any_pointer_state_changed();
         let pointer_pos_changed = has_any_pointer_position_changed();
         let scroll_changed = has_scroll_changed();
+        let zoom_changed = has_zoom_changed();
         let active_pointer_count = get_active_pointer_count();
         let last_active_pointer_count = get_last_active_pointer_count();
         let mut should_draw = false;
-        if pointer_pos_changed || pointer_state_changed || scroll_changed {
-            if active_pointer_count == 1 {
-                let pointer_pos = get_pointer_position(0);
-                let last_pointer_pos = get_last_pointer_position(0);
+        if pointer_pos_changed || pointer_state_changed || scroll_changed || zoom_changed {
+            // whichever slot the remaining pointer is in, it isn't always 0
+            let slot = (0..POINTER_COUNT).find(|i| get_pointer_position(*i).is_some());
+            if let (1, Some(slot)) = (active_pointer_count, slot) {
+                let pointer_pos = get_pointer_position(slot);
+                let last_pointer_pos = get_last_pointer_position(slot);
 
                 if let (true, Some(pointer_pos), Some(last_pointer_pos)) =
-                    (pointer_btn(0, 0), pointer_pos, last_pointer_pos)
+                    (pointer_btn(slot as u8, 0), pointer_pos, last_pointer_pos)
                 {
                     self.pan_update(pointer_pos, last_pointer_pos);
                     should_draw = true;
                 }
 
-                if let (true, Some(pointer_pos)) = (scroll_changed, pointer_pos) {
-                    let (_, scroll_delta) = get_scroll();
-                    self.scroll_update(pointer_pos, scroll_delta);
+                if scroll_changed {
+                    let (dx, dy, _) = get_scroll_delta();
+                    self.pan_by(dx, dy);
                     should_draw = true;
                 }
-            } else if let (
-                true,
-                Some(primary_pointer_pos),
-                Some(last_primary_pointer_pos),
-                Some(secondary_pointer_pos),
-                Some(last_secondary_pointer_pos),
-            ) = (
-                active_pointer_count > 1,
-                get_pointer_position(0),
-                get_last_pointer_position(0),
-                get_pointer_position(1),
-                get_last_pointer_position(1),
-            ) {
-                self.multitouch_update(
-                    primary_pointer_pos,
-                    last_primary_pointer_pos,
-                    secondary_pointer_pos,
-                    last_secondary_pointer_pos,
-                );
-                should_draw = true;
+
+                if let (true, Some(pointer_pos)) = (zoom_changed, pointer_pos) {
+                    let (_, zoom_delta) = get_zoom();
+                    self.scroll_update(pointer_pos, zoom_delta);
+                    should_draw = true;
+                }
+            }
+        }
+        // two finger pan and pinch
+        for gesture in self.gestures.update(new_time) {
+            match gesture {
+                Gesture::Pan { dx, dy, .. } => {
+                    self.pan_by(-dx as f64, -dy as f64);
+                    should_draw = true;
+                }
+                Gesture::Pinch {
+                    x,
+                    y,
+                    distance,
+                    last_distance,
+                    ..
+                } => {
+                    let center = Point {
+                        x: x as i32,
+                        y: y as i32,
+                    };
+                    let diff_length = (distance - last_distance) as f64 / self.width * 2.0;
+                    self.scroll_update(center, diff_length * 100.0);
+                    should_draw = true;
+                }
+                _ => {}
             }
         }
 
@@ -117,10 +134,12 @@ impl Mandlebrot {
             y: last_y,
         }: Point,
     ) {
-        let (diff_x, diff_y) = (
-            (last_x - x) as f64 / self.width,
-            (last_y - y) as f64 / self.height,
-        );
+        self.pan_by((last_x - x) as f64, (last_y - y) as f64);
+    }
+
+    // Moves the view by a distance in screen pixels.
+    fn pan_by(&mut self, dx: f64, dy: f64) {
+        let (diff_x, diff_y) = (dx / self.width, dy / self.height);
 
         let width = self.clip_rect.r - self.clip_rect.l;
         let height = self.clip_rect.b - self.clip_rect.t;
@@ -172,60 +191,6 @@ impl Mandlebrot {
         self.clip_rect = new_clip_rect;
     }
 
-    fn multitouch_update(
-        &mut self,
-        Point {
-            x: primary_x,
-            y: primary_y,
-        }: Point,
-        Point {
-            x: last_primary_x,
-            y: last_primary_y,
-        }: Point,
-        Point {
-            x: secondary_x,
-            y: secondary_y,
-        }: Point,
-        Point {
-            x: last_secondary_x,
-            y: last_secondary_y,
-        }: Point,
-    ) {
-        let center_x = (primary_x + secondary_x) / 2;
-        let center_y = (primary_y + secondary_y) / 2;
-        let last_center_x = (last_primary_x + last_secondary_x) / 2;
-        let last_center_y = (last_primary_y + last_secondary_y) / 2;
-        self.pan_update(
-            Point {
-                x: center_x,
-                y: center_y,
-            },
-            Point {
-                x: last_center_x,
-                y: last_center_y,
-            },
-        );
-
-        let length = {
-            let x = (secondary_x - primary_x) as f64 / self.width;
-            let y = (secondary_y - primary_y) as f64 / self.height;
-            ((x * x) + (y * y)).sqrt()
-        };
-
-        let last_length = {
-            let x = (last_secondary_x - last_primary_x) as f64 / self.width;
-            let y = (last_secondary_y - last_primary_y) as f64 / self.height;
-            ((x * x) + (y * y)).sqrt()
-        };
-        let diff_length = (length - last_length) * 2.0;
-        self.scroll_update(
-            Point {
-                x: center_x,
-                y: center_y,
-            },
-            diff_length * 100.0,
-        );
-    }
     /*
         fn update_fractal_and_draw(
             &mut self,
@@ -379,4 +344,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "mandlebrot",
+    description: "Pan and zoom around the Mandlebrot set with a drag, the wheel or two fingers.",
+    author: "HelveticaScenario",
+    tags: &["fractal", "interactive", "gestures"],
+    date: None,
+    resolution: Some((224, 128)),
+    inputs: &[SketchInput::Pointer, SketchInput::Multitouch, SketchInput::Wheel],
+    thumbnail: None,
 };
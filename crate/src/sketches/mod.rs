@@ -28,3 +28,42 @@ pub static SKETCHES: &[&SketchDescriptor] = &[
     &drag::sketch,
     &path_drawing::sketch,
 ];
+
+// Index of the sketch served at url, with or without slashes around it.
+pub fn find_sketch(url: &str) -> Option<usize> {
+    let url = url.trim_matches('/');
+    SKETCHES.iter().position(|sketch| sketch.url == url)
+}
+
+pub fn catalog_json() -> String {
+    let sketches: Vec<String> = SKETCHES
+        .iter()
+        .enumerate()
+        .map(|(i, sketch)| sketch.to_json(i))
+        .collect();
+    format!("[{}]", sketches.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_every_sketch_by_url() {
+        for (i, sketch) in SKETCHES.iter().enumerate() {
+            assert_eq!(find_sketch(sketch.url), Some(i));
+            assert_eq!(find_sketch(&format!("/{}/", sketch.url)), Some(i));
+        }
+        assert_eq!(find_sketch("no-such-sketch"), None);
+    }
+
+    #[test]
+    fn catalog_has_an_entry_per_sketch() {
+        let catalog = catalog_json();
+        assert!(catalog.starts_with('[') && catalog.ends_with(']'));
+        for (i, sketch) in SKETCHES.iter().enumerate() {
+            assert!(catalog.contains(&format!("{{\"index\":{},", i)));
+            assert!(catalog.contains(&format!("\"url\":\"{}\"", sketch.url)));
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::document::*;
 use crate::pico::*;
 use crate::sketch::*;
 use euclid::Point2D;
@@ -7,8 +8,12 @@ use web_sys::console::log_1;
 
 use std::cmp;
 
+const STROKE_COLOR: i32 = 7;
+const STROKE_WIDTH: f64 = 6.0;
+
 pub struct PathDrawing {
     pub points: Vec<Point2D<f64>>,
+    pub strokes: Vec<Stroke>,
 }
 
 impl Sketch for PathDrawing {
@@ -17,30 +22,63 @@ impl Sketch for PathDrawing {
         cls(0);
         set_target(1);
         cls(0);
-        PathDrawing { points: vec![] }
+        PathDrawing {
+            points: vec![],
+            strokes: vec![],
+        }
     }
     fn update(&mut self, new_time: f32, old_time: f32) {
-        let mouse_pos = get_pointer_position(0);
-        let last_mouse_pos = get_last_pointer_position(0);
-        if !pointer_btn(0, 0) && pointer_btn_this_frame(0, 0) {
-            set_target(1);
-            fat_line_strip(&self.points, 3.0, 7);
-            self.points = vec![];
+        set_target(1);
+        for event in take_pointer_events() {
+            if event.pointer != 0 {
+                continue;
+            }
+            let m = Point2D::new(event.x as f64, event.y as f64);
+            match event.kind {
+                PointerEventKind::Down => {
+                    self.points.clear();
+                    self.points.push(m);
+                }
+                PointerEventKind::Move if event.btn(0) => {
+                    if self.points.last() != Some(&m) {
+                        self.points.push(m);
+                    }
+                }
+                PointerEventKind::Up | PointerEventKind::End if !self.points.is_empty() => {
+                    let stroke = Stroke {
+                        points: self.points.split_off(0),
+                        color: STROKE_COLOR,
+                        width: STROKE_WIDTH,
+                    };
+                    stroke.draw();
+                    self.strokes.push(stroke);
+                }
+                _ => {}
+            }
         }
         set_target(0);
         cls(0);
         copy_screen(1, 0);
-        if let (Some(Point { x: new_x, y: new_y }), Some(Point { x: old_x, y: old_y })) =
-            (mouse_pos, last_mouse_pos)
-        {
-            let m = Point2D::new(new_x as f64, new_y as f64);
-            if (new_x != old_x || new_y != old_y) && pointer_btn(0, 0) {
-                self.points.push(m.clone());
-            }
-            circ_euclid(&m, 8.0, 9);
+        if let Some(Point { x, y }) = get_pointer_position(0) {
+            circ_euclid(&Point2D::new(x as f64, y as f64), 8.0, 9);
         }
 
-        fat_line_strip(&self.points, 3.0, 12);
+        fat_line_strip(&self.points, STROKE_WIDTH / 2.0, 12);
+    }
+    fn save(&self) -> Option<Document> {
+        let mut document = Document::new(WIDTH(), HEIGHT());
+        document.strokes = self.strokes.clone();
+        Some(document)
+    }
+    fn load(&mut self, document: &Document) -> bool {
+        if document.width != WIDTH() || document.height != HEIGHT() {
+            return false;
+        }
+        self.points = vec![];
+        self.strokes = document.strokes.clone();
+        let loaded = document.draw(1).is_ok();
+        set_target(0);
+        loaded
     }
 }
 
@@ -55,4 +93,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "path-drawing",
+    description: "Draw smooth fat strokes that are kept as vectors. Saves, loads and exports svg.",
+    author: "HelveticaScenario",
+    tags: &["drawing", "interactive", "vector"],
+    date: None,
+    resolution: Some((512, 512)),
+    inputs: &[SketchInput::Pointer],
+    thumbnail: None,
 };
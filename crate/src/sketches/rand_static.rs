@@ -22,6 +22,8 @@ impl Sketch for RandStatic {
                 screen[i] = num % 16;
             }
         }
+        // raw writes skip the dirty tracking the drawing functions do
+        mark_screen_dirty(0);
     }
 }
 
@@ -36,4 +38,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "random-static",
+    description: "Every pixel gets a random color every frame.",
+    author: "HelveticaScenario",
+    tags: &["generative", "noise"],
+    date: None,
+    resolution: None,
+    inputs: &[],
+    thumbnail: None,
 };
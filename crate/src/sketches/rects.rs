@@ -41,4 +41,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "rectangles",
+    description: "One random rectangle outline a frame, piling up forever.",
+    author: "HelveticaScenario",
+    tags: &["generative", "rects"],
+    date: None,
+    resolution: None,
+    inputs: &[],
+    thumbnail: None,
 };
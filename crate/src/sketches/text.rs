@@ -1,3 +1,6 @@
+use crate::font::*;
+use crate::layout::*;
+use crate::markup::*;
 use crate::pico::*;
 use crate::sketch::*;
 use std::cell::RefCell;
@@ -62,30 +65,39 @@ impl Sketch for Text {
         if self.count % 16 == 0 {
             cls(0);
             let offset = Point { x: 2, y: 2 };
+            let (style, name) = if (self.count % 32) == 0 {
+                ("{outline:7}", "outline")
+            } else {
+                ("{shadow:7}", "drop shadow")
+            };
             prnt(
-                &string,
-                offset.x - 1,
-                if (self.count % 32) == 0 {
-                    offset.y
-                } else {
-                    offset.y - 1
-                },
-                3,
-                if (self.count % 32) == 0 { 3 } else { 4 },
-                7,
-            );
-            prnt(
-                &string,
+                &format!("{}{}", style, escape_markup(&string)),
+                DEFAULT_FONT,
                 offset.x,
-                if (self.count % 32) == 0 {
-                    offset.y + 1
-                } else {
-                    offset.y
-                },
+                offset.y,
                 1,
                 1,
                 0,
             );
+            let (width, height) = text_size(string, DEFAULT_FONT);
+            let caption = format!("{} around {}x{} pixels of source", name, width, height);
+            let layout = TextLayout {
+                h_align: HAlign::Right,
+                v_align: VAlign::Bottom,
+                ..TextLayout::default()
+            };
+            prnt_layout(
+                &caption,
+                SMALL_FONT,
+                WIDTH() as i32 - 122,
+                HEIGHT() as i32 - 42,
+                120,
+                40,
+                &layout,
+                1,
+                1,
+                12,
+            );
         }
     }
 }
@@ -101,4 +113,11 @@ pub static sketch: SketchDescriptor = SketchDescriptor {
     desktop: true,
     public: true,
     url: "text",
+    description: "A page of Rust source drawn with markup, switching between an outline and a drop shadow.",
+    author: "HelveticaScenario",
+    tags: &["text", "font"],
+    date: None,
+    resolution: Some((480, 272)),
+    inputs: &[],
+    thumbnail: None,
 };
@@ -0,0 +1,91 @@
+extern crate rust_webpack;
+
+use rust_webpack::asset::*;
+use rust_webpack::pico::*;
+use std::sync::Mutex;
+
+// the drawing state is global, so these run one at a time
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn asset(width: usize, height: usize) -> PicoAsset {
+    PicoAsset {
+        width,
+        height,
+        palette: None,
+        pixels: (0..(width * height)).map(|i| (i / 3) as u8).collect(),
+        sprite_flags: None,
+        map: None,
+        metadata: vec![],
+    }
+}
+
+fn fill_buffer_3(width: usize, height: usize, c: u8) {
+    set_dimensions(width, height);
+    screen(3)[..(width * height)].fill(c);
+}
+
+#[test]
+fn rle_pixels_are_decoded_at_the_screen_stride() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    fill_buffer_3(16, 16, 9);
+    let sprite = asset(5, 3);
+    load_spritesheet(&write_pico_with(&sprite, Compression::Rle)).unwrap();
+    let buffer = screen(3);
+    for y in 0..16 {
+        for x in 0..16 {
+            let expected = if x < 5 && y < 3 {
+                sprite.pixels[y * 5 + x]
+            } else {
+                9
+            };
+            assert_eq!(buffer[y * 16 + x], expected, "{} {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn corrupt_rle_leaves_buffer_3_untouched() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    fill_buffer_3(16, 16, 9);
+    let mut bytes = write_pico_with(&asset(8, 8), Compression::Rle);
+    // drop the last run, the chunk length is patched to match
+    let len = bytes.len() - 6 - 8 - 2;
+    bytes.truncate(bytes.len() - 2);
+    bytes[10..14].copy_from_slice(&(len as u32).to_le_bytes());
+    assert!(load_spritesheet(&bytes).is_err());
+    assert!(screen(3)[..256].iter().all(|p| *p == 9));
+}
+
+#[test]
+fn size_limits_do_not_depend_on_the_screen() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    set_dimensions(16, 16);
+    for compression in &[Compression::None, Compression::Rle] {
+        let bytes = write_pico_with(&asset(64, 64), *compression);
+        assert_eq!(parse_pico(&bytes).unwrap(), asset(64, 64));
+        assert_eq!(
+            load_spritesheet(&bytes),
+            Err(AssetError::TooLarge {
+                width: 64,
+                height: 64,
+                max_width: 16,
+                max_height: 16,
+            })
+        );
+    }
+    let too_large = AssetError::TooLarge {
+        width: MAX_WIDTH + 1,
+        height: 1,
+        max_width: MAX_WIDTH,
+        max_height: MAX_HEIGHT,
+    };
+    let mut v1 = vec![0; 4 + NUM_COLORS * 3];
+    v1[0..2].copy_from_slice(&(MAX_WIDTH as u16 + 1).to_le_bytes());
+    v1[2..4].copy_from_slice(&1u16.to_le_bytes());
+    assert_eq!(parse_pico(&v1), Err(too_large));
+    let wide = write_pico_with(&asset(MAX_WIDTH + 1, 1), Compression::Rle);
+    assert!(match parse_pico(&wide) {
+        Err(AssetError::TooLarge { max_width, .. }) => max_width == MAX_WIDTH,
+        _ => false,
+    });
+}
@@ -0,0 +1,189 @@
+extern crate rust_webpack;
+
+use rust_webpack::pico::*;
+use std::sync::Mutex;
+
+// the drawing state is global, so these run one at a time
+static LOCK: Mutex<()> = Mutex::new(());
+
+const SIZE: usize = 64;
+
+fn reset() {
+    set_dimensions(SIZE, SIZE);
+    set_target(0);
+    camera_set(0, 0);
+    for i in 0..4 {
+        screen(i)[..(SIZE * SIZE)].fill(0);
+    }
+}
+
+fn canvas(i: u8) -> Vec<u8> {
+    screen(i)[..(SIZE * SIZE)].to_vec()
+}
+
+fn fill_pattern(i: u8) {
+    for (p, pixel) in screen(i)[..(SIZE * SIZE)].iter_mut().enumerate() {
+        *pixel = (p * 7 % 13) as u8 + 1;
+    }
+}
+
+fn on_screen(x: i32, y: i32) -> bool {
+    x >= 0 && y >= 0 && x < SIZE as i32 && y < SIZE as i32
+}
+
+// The blit as it used to be done, one bounds checked pixel at a time.
+fn copy_per_pixel(
+    source: &[u8],
+    target: &mut [u8],
+    source_x: i32,
+    source_y: i32,
+    target_x: i32,
+    target_y: i32,
+    width: i32,
+    height: i32,
+    transparent: &[u8],
+) {
+    for y in 0..height {
+        for x in 0..width {
+            let (sx, sy) = (source_x + x, source_y + y);
+            let (tx, ty) = (target_x + x, target_y + y);
+            if on_screen(sx, sy) && on_screen(tx, ty) {
+                let pixel = source[sy as usize * SIZE + sx as usize];
+                if !transparent.contains(&pixel) {
+                    target[ty as usize * SIZE + tx as usize] = pixel;
+                }
+            }
+        }
+    }
+}
+
+const EDGE_BLITS: [(i32, i32, i32, i32, i32, i32); 8] = [
+    (0, 0, -5, -7, 16, 16),
+    (0, 0, 56, 60, 16, 16),
+    (-4, 10, 10, 10, 16, 8),
+    (50, 50, 0, 0, 20, 20),
+    (10, 10, -20, 10, 16, 16),
+    (0, 0, 0, 0, 64, 64),
+    (-70, 0, 0, 0, 16, 16),
+    (3, -2, 60, -3, 64, 64),
+];
+
+#[test]
+fn clipped_blits_match_the_per_pixel_copy() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    for &(sx, sy, tx, ty, w, h) in EDGE_BLITS.iter() {
+        reset();
+        fill_pattern(1);
+        copy_sprite(1, 0, sx, sy, tx, ty, w as usize, h as usize);
+        let mut expected = vec![0; SIZE * SIZE];
+        copy_per_pixel(&canvas(1), &mut expected, sx, sy, tx, ty, w, h, &[]);
+        assert_eq!(
+            canvas(0),
+            expected,
+            "copy_sprite {:?}",
+            (sx, sy, tx, ty, w, h)
+        );
+    }
+}
+
+#[test]
+fn clipped_transparent_blits_match_the_per_pixel_copy() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    palt(3, true);
+    palt(5, true);
+    for &(sx, sy, tx, ty, w, h) in EDGE_BLITS.iter() {
+        reset();
+        fill_pattern(1);
+        screen(0)[..(SIZE * SIZE)].fill(9);
+        copy_sprite_with_transparency(1, 0, sx, sy, tx, ty, w as usize, h as usize);
+        let mut expected = vec![9; SIZE * SIZE];
+        copy_per_pixel(&canvas(1), &mut expected, sx, sy, tx, ty, w, h, &[3, 5]);
+        assert_eq!(
+            canvas(0),
+            expected,
+            "copy_sprite_with_transparency {:?}",
+            (sx, sy, tx, ty, w, h)
+        );
+    }
+    palt(3, false);
+    palt(5, false);
+}
+
+#[test]
+fn overlapping_blits_read_the_source_before_writing() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    for &(sx, sy, tx, ty) in [(0, 0, 3, 5), (3, 5, 0, 0), (10, 10, 12, 9), (-2, 40, 4, 38)].iter() {
+        reset();
+        fill_pattern(0);
+        let before = canvas(0);
+        copy_sprite(0, 0, sx, sy, tx, ty, 32, 32);
+        let mut expected = before.clone();
+        copy_per_pixel(&before, &mut expected, sx, sy, tx, ty, 32, 32, &[]);
+        assert_eq!(canvas(0), expected, "overlapping {:?}", (sx, sy, tx, ty));
+    }
+}
+
+#[test]
+fn clipped_rect_fills_match_the_per_pixel_fill() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let rects = [
+        (-10, -10, 5, 5),
+        (60, 60, 100, 100),
+        (70, 10, -5, 20),
+        (-100, -100, 200, 200),
+        (64, 0, 80, 10),
+        (10, 63, 20, 63),
+    ];
+    for &(x0, y0, x1, y1) in rects.iter() {
+        reset();
+        rect_fill(x0, y0, x1, y1, 7);
+        let mut expected = vec![0; SIZE * SIZE];
+        for y in y0.min(y1)..=y0.max(y1) {
+            for x in x0.min(x1)..=x0.max(x1) {
+                if on_screen(x, y) {
+                    expected[y as usize * SIZE + x as usize] = 7;
+                }
+            }
+        }
+        assert_eq!(canvas(0), expected, "rect_fill {:?}", (x0, y0, x1, y1));
+    }
+}
+
+#[test]
+fn clipped_circle_fills_match_the_unclipped_circle() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let center = SIZE as i32 / 2;
+    for &r in [2, 5, 10, 20].iter() {
+        reset();
+        circ_fill(center, center, r, 7);
+        let whole = canvas(0);
+        let circles = [
+            (0, 0, None),
+            (-5, 20, None),
+            (63, 63, None),
+            (70, 10, None),
+            (20, -9, None),
+            (32, 32, Some((28, 30, 10, 6))),
+            (5, 60, Some((0, 50, 8, 20))),
+        ];
+        for &(cx, cy, clip) in circles.iter() {
+            reset();
+            if let Some((x, y, w, h)) = clip {
+                set_clip(x, y, w, h);
+            }
+            let (l, t, w, h) = clip.unwrap_or((0, 0, SIZE as i32, SIZE as i32));
+            circ_fill(cx, cy, r, 7);
+            let mut expected = vec![0; SIZE * SIZE];
+            for y in t..(t + h) {
+                for x in l..(l + w) {
+                    let (wx, wy) = (x - cx + center, y - cy + center);
+                    if on_screen(x, y) && on_screen(wx, wy) {
+                        expected[y as usize * SIZE + x as usize] =
+                            whole[wy as usize * SIZE + wx as usize];
+                    }
+                }
+            }
+            assert_eq!(canvas(0), expected, "circ_fill {:?}", (cx, cy, r, clip));
+        }
+    }
+}
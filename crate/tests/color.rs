@@ -0,0 +1,65 @@
+extern crate rust_webpack;
+
+use rust_webpack::asset::set_palette;
+use rust_webpack::color::*;
+use std::sync::Mutex;
+
+// PALETTE is global, so these run one at a time
+static LOCK: Mutex<()> = Mutex::new(());
+
+// black, red, green, blue, white
+const COLORS: [u8; 15] = [0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+
+#[test]
+fn cached_lookups_follow_palette_changes() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    set_palette(&COLORS);
+    let orange = [255, 128, 0];
+    assert_eq!(nearest_color_cached(&orange, 5, ColorMetric::Rgb), 1);
+    // replacing green with orange has to rebuild the table
+    set_palette(&[0, 0, 0, 255, 0, 0, 255, 128, 0]);
+    assert_eq!(nearest_color_cached(&orange, 5, ColorMetric::Rgb), 2);
+    // and so does using fewer colors
+    assert_eq!(nearest_color_cached(&orange, 2, ColorMetric::Rgb), 1);
+    // or another metric
+    set_palette(&[0, 0, 0, 0, 0, 255]);
+    let dark_blue = [0, 0, 110];
+    assert_eq!(nearest_color_cached(&dark_blue, 2, ColorMetric::Rgb), 0);
+    assert_eq!(nearest_color_cached(&dark_blue, 2, ColorMetric::Lab), 1);
+}
+
+#[test]
+fn cached_lookups_match_exact_ones_for_palette_colors() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    set_palette(&COLORS);
+    for (i, rgb) in COLORS.chunks(3).enumerate() {
+        for &metric in [ColorMetric::Rgb, ColorMetric::Lab].iter() {
+            assert_eq!(nearest_color_cached(rgb, 5, metric), i as u8);
+            assert_eq!(nearest_color(rgb, 5, metric), i as u8);
+        }
+    }
+}
+
+#[test]
+fn fill_ramp_blends_between_the_ends() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    set_palette(&[0, 0, 0, 1, 1, 1, 2, 2, 2, 3, 3, 3, 200, 100, 40]);
+    // either order fills the same entries
+    fill_ramp(4, 0);
+    let filled: Vec<[u8; 3]> = (0..5).map(palette_color).collect();
+    assert_eq!(
+        filled,
+        vec![[0, 0, 0], [50, 25, 10], [100, 50, 20], [150, 75, 30], [200, 100, 40]]
+    );
+}
+
+#[test]
+fn ramp_indices_walks_the_existing_colors() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    set_palette(&[0, 0, 0, 255, 0, 0, 85, 85, 85, 170, 170, 170, 255, 255, 255]);
+    assert_eq!(ramp_indices(0, 4, 9, 5), vec![0, 2, 3, 4]);
+    // repeats are dropped, so fewer steps than colors skips some
+    assert_eq!(ramp_indices(0, 4, 2, 5), vec![0, 4]);
+    // only the first count colors are candidates
+    assert_eq!(ramp_indices(0, 1, 5, 2), vec![0, 1]);
+}
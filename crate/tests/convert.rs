@@ -0,0 +1,147 @@
+extern crate rust_webpack;
+
+use rust_webpack::asset::{parse_pico, Compression};
+use rust_webpack::color::ColorMetric;
+use rust_webpack::convert::*;
+
+fn fixture(name: &str) -> Vec<u8> {
+    std::fs::read(format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name))
+        .expect(name)
+}
+
+fn options(palette: PaletteSource) -> ConvertOptions {
+    ConvertOptions {
+        palette,
+        ..ConvertOptions::default()
+    }
+}
+
+#[test]
+fn exact_colors_map_to_their_pico8_indices() {
+    for metric in &[ColorMetric::Rgb, ColorMetric::Lab] {
+        let bytes = png_to_pico(
+            &fixture("pico8_rgb.png"),
+            &ConvertOptions {
+                metric: *metric,
+                ..options(PaletteSource::Fixed(pico8_palette()))
+            },
+        )
+        .unwrap();
+        let asset = parse_pico(&bytes).unwrap();
+        assert_eq!((asset.width, asset.height), (4, 2));
+        assert_eq!(asset.pixels, vec![0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(asset.palette, Some(pico8_palette()));
+    }
+}
+
+#[test]
+fn indexed_png_keeps_its_palette_and_indices() {
+    let image = decode_png(&fixture("indexed.png")).unwrap();
+    let asset = image_to_asset(&image, &options(PaletteSource::FromImage)).unwrap();
+    assert_eq!(asset.palette, Some(vec![255, 0, 0, 0, 255, 0, 0, 0, 255]));
+    assert_eq!(asset.pixels, vec![0, 1, 2, 2, 1, 0]);
+}
+
+#[test]
+fn transparent_pixels_get_the_transparent_index() {
+    let image = decode_png(&fixture("transparent_rgba.png")).unwrap();
+    let asset = image_to_asset(
+        &image,
+        &ConvertOptions {
+            transparent_index: Some(15),
+            ..options(PaletteSource::Fixed(pico8_palette()))
+        },
+    )
+    .unwrap();
+    assert_eq!(asset.pixels, vec![0, 15, 8, 15]);
+}
+
+#[test]
+fn grayscale_is_expanded_before_matching() {
+    let image = decode_png(&fixture("gray.png")).unwrap();
+    assert_eq!(image.rgba, vec![0, 0, 0, 255, 255, 255, 255, 255]);
+    let palette = vec![0, 0, 0, 255, 255, 255];
+    let asset = image_to_asset(&image, &options(PaletteSource::Fixed(palette))).unwrap();
+    assert_eq!(asset.pixels, vec![0, 1]);
+}
+
+#[test]
+fn quantize_keeps_distinct_colors_apart() {
+    let image = decode_png(&fixture("pico8_rgb.png")).unwrap();
+    let asset = image_to_asset(&image, &options(PaletteSource::Quantize(8))).unwrap();
+    let palette = asset.palette.unwrap();
+    assert_eq!(palette.len(), 8 * 3);
+    let mut pixels = asset.pixels.clone();
+    pixels.sort();
+    pixels.dedup();
+    assert_eq!(pixels.len(), 8);
+}
+
+#[test]
+fn palette_with_a_partial_triple_ignores_the_extra_bytes() {
+    let image = decode_png(&fixture("gray.png")).unwrap();
+    let palette = vec![0, 0, 0, 255, 255, 255, 7];
+    let asset = image_to_asset(&image, &options(PaletteSource::Fixed(palette))).unwrap();
+    assert_eq!(asset.palette, Some(vec![0, 0, 0, 255, 255, 255]));
+    assert_eq!(asset.pixels, vec![0, 1]);
+}
+
+#[test]
+fn palette_without_a_whole_color_is_rejected() {
+    let image = decode_png(&fixture("gray.png")).unwrap();
+    for palette in vec![vec![], vec![255, 255]] {
+        match image_to_asset(&image, &options(PaletteSource::Fixed(palette))) {
+            Err(ConvertError::EmptyPalette) => {}
+            _ => panic!("expected EmptyPalette"),
+        }
+    }
+}
+
+#[test]
+fn rle_output_decodes_to_the_same_pixels() {
+    let raw = png_to_pico(
+        &fixture("pico8_rgb.png"),
+        &options(PaletteSource::Fixed(pico8_palette())),
+    )
+    .unwrap();
+    let rle = png_to_pico(
+        &fixture("pico8_rgb.png"),
+        &ConvertOptions {
+            compression: Compression::Rle,
+            ..options(PaletteSource::Fixed(pico8_palette()))
+        },
+    )
+    .unwrap();
+    assert_eq!(parse_pico(&raw).unwrap(), parse_pico(&rle).unwrap());
+}
+
+#[test]
+fn garbage_is_a_decode_error() {
+    match decode_png(b"not a png") {
+        Err(ConvertError::Decode(_)) => {}
+        _ => panic!("expected Decode"),
+    }
+}
+
+#[test]
+fn packed_indexed_png_keeps_its_indices() {
+    let image = decode_png(&fixture("indexed4.png")).unwrap();
+    let asset = image_to_asset(&image, &options(PaletteSource::FromImage)).unwrap();
+    assert_eq!(asset.palette, Some(vec![255, 0, 0, 0, 255, 0, 0, 0, 255]));
+    assert_eq!(asset.pixels, vec![0, 1, 2, 1, 0, 2, 2, 0, 1, 1]);
+}
+
+#[test]
+fn indexed_png_transparency_gets_the_transparent_index() {
+    // index 2 is fully transparent in the png's tRNS chunk
+    let image = decode_png(&fixture("indexed4.png")).unwrap();
+    let asset = image_to_asset(
+        &image,
+        &ConvertOptions {
+            transparent_index: Some(9),
+            ..options(PaletteSource::FromImage)
+        },
+    )
+    .unwrap();
+    assert_eq!(asset.pixels, vec![0, 1, 9, 1, 0, 9, 9, 0, 1, 1]);
+}
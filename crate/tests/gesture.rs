@@ -0,0 +1,121 @@
+extern crate rust_webpack;
+
+use rust_webpack::gesture::*;
+use rust_webpack::pico::*;
+use std::sync::Mutex;
+
+// pointer state is global, so these run one at a time
+static LOCK: Mutex<()> = Mutex::new(());
+
+const ID: i32 = 1;
+
+fn press(x: i32, y: i32) {
+    let slot = begin_pointer(ID, x, y, 0.0).unwrap();
+    STATE.0.borrow_mut().pointer_state[slot] = 1;
+}
+
+fn move_to(x: i32, y: i32) {
+    let slot = get_pointer_slot(ID).unwrap();
+    STATE.0.borrow_mut().pointer_pos[slot] = Some(Point { x, y });
+}
+
+fn release() {
+    end_pointer(ID, 0.0);
+}
+
+fn tap(recognizer: &mut GestureRecognizer, x: i32, y: i32, time: f32) -> Vec<Gesture> {
+    press(x, y);
+    assert_eq!(recognizer.update(time), vec![]);
+    release();
+    recognizer.update(time + 50.0)
+}
+
+#[test]
+fn quick_presses_are_taps() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+    assert_eq!(
+        tap(&mut recognizer, 10, 20, 0.0),
+        vec![Gesture::Tap { x: 10, y: 20 }]
+    );
+    // held too long, that is a long press rather than a tap
+    press(10, 20);
+    recognizer.update(1000.0);
+    assert_eq!(
+        recognizer.update(1600.0),
+        vec![Gesture::LongPress { x: 10, y: 20 }]
+    );
+    release();
+    assert_eq!(recognizer.update(1650.0), vec![]);
+}
+
+#[test]
+fn second_tap_nearby_is_a_double_tap() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+    tap(&mut recognizer, 10, 20, 0.0);
+    assert_eq!(
+        tap(&mut recognizer, 12, 21, 150.0),
+        vec![Gesture::DoubleTap { x: 12, y: 21 }]
+    );
+    // a third tap starts over
+    assert_eq!(
+        tap(&mut recognizer, 12, 21, 300.0),
+        vec![Gesture::Tap { x: 12, y: 21 }]
+    );
+    // too late, or too far away, for a double tap
+    assert_eq!(
+        tap(&mut recognizer, 12, 21, 1000.0),
+        vec![Gesture::Tap { x: 12, y: 21 }]
+    );
+    assert_eq!(
+        tap(&mut recognizer, 40, 21, 1100.0),
+        vec![Gesture::Tap { x: 40, y: 21 }]
+    );
+}
+
+#[test]
+fn fast_drags_end_in_a_fling() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+    press(0, 0);
+    recognizer.update(0.0);
+    move_to(10, 0);
+    assert_eq!(
+        recognizer.update(16.0),
+        vec![
+            Gesture::DragStart { x: 0, y: 0 },
+            Gesture::Drag { x: 10, y: 0, dx: 10, dy: 0 },
+        ]
+    );
+    move_to(26, 8);
+    recognizer.update(32.0);
+    release();
+    let gestures = recognizer.update(48.0);
+    assert_eq!(gestures[0], Gesture::DragEnd { x: 26, y: 8 });
+    // measured from the first sample at 0, 0
+    match gestures[1] {
+        Gesture::Fling { vx, vy } => {
+            assert!((vx - 26.0 / 32.0).abs() < 1e-6);
+            assert!((vy - 8.0 / 32.0).abs() < 1e-6);
+        }
+        gesture => panic!("expected a fling, found {:?}", gesture),
+    }
+}
+
+#[test]
+fn slow_drags_do_not_fling() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut recognizer = GestureRecognizer::new(GestureConfig::default());
+    press(0, 0);
+    recognizer.update(0.0);
+    for step in 1..=10 {
+        move_to(step * 2, 0);
+        recognizer.update(step as f32 * 50.0);
+    }
+    release();
+    assert_eq!(
+        recognizer.update(550.0),
+        vec![Gesture::DragEnd { x: 20, y: 0 }]
+    );
+}
@@ -0,0 +1,129 @@
+extern crate rust_webpack;
+
+use rust_webpack::font::*;
+use rust_webpack::layout::*;
+use rust_webpack::pico::*;
+use std::sync::Mutex;
+
+// the drawing state is global, so these run one at a time
+static LOCK: Mutex<()> = Mutex::new(());
+
+const SIZE: usize = 64;
+
+fn reset() {
+    set_dimensions(SIZE, SIZE);
+    set_target(0);
+    camera_set(0, 0);
+    screen(0)[..(SIZE * SIZE)].fill(0);
+}
+
+fn pixel(x: i32, y: i32) -> u8 {
+    screen(0)[y as usize * SIZE + x as usize]
+}
+
+#[test]
+fn scaled_text_is_the_1x_text_with_bigger_pixels() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    reset();
+    let small = prnt("Hi{c:8}!", DEFAULT_FONT, 0, 0, 1, 1, 7);
+    let ones = screen(0)[..(SIZE * SIZE)].to_vec();
+    assert!(ones.contains(&7) && ones.contains(&8));
+    reset();
+    let big = prnt("Hi{c:8}!", DEFAULT_FONT, 0, 0, 2, 3, 7);
+    assert_eq!((big.x, big.y), (small.x * 2, small.y));
+    for y in 0..(SIZE as i32 / 3) {
+        for x in 0..(SIZE as i32 / 2) {
+            assert_eq!(
+                pixel(x * 2 + 1, y * 3 + 2),
+                ones[y as usize * SIZE + x as usize],
+                "{} {}",
+                x,
+                y
+            );
+        }
+    }
+}
+
+#[test]
+fn newlines_step_by_the_scaled_line_height() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    reset();
+    let one = prnt("a\nb", DEFAULT_FONT, 0, 0, 1, 1, 7);
+    let two = prnt("a\nb", DEFAULT_FONT, 0, 0, 1, 2, 7);
+    assert_eq!(two.y, one.y * 2);
+}
+
+// Bounding box of the pixels drawn in color c, as (l, t, r, b) exclusive.
+fn drawn(c: u8) -> Option<(i32, i32, i32, i32)> {
+    let mut bounds: Option<(i32, i32, i32, i32)> = None;
+    for y in 0..(SIZE as i32) {
+        for x in 0..(SIZE as i32) {
+            if pixel(x, y) == c {
+                let (l, t, r, b) = bounds.unwrap_or((x, y, x + 1, y + 1));
+                bounds = Some((l.min(x), t.min(y), r.max(x + 1), b.max(y + 1)));
+            }
+        }
+    }
+    bounds
+}
+
+fn boxed(string: &str, h_align: HAlign, v_align: VAlign, wrap: bool, height: i32) {
+    let layout = TextLayout {
+        h_align,
+        v_align,
+        wrap,
+        ellipsis: false,
+    };
+    prnt_layout(string, DEFAULT_FONT, 8, 8, 40, height, &layout, 1, 1, 7);
+}
+
+#[test]
+fn alignment_moves_the_text_within_the_box() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let (tw, th) = text_size("ab", DEFAULT_FONT);
+    reset();
+    boxed("ab", HAlign::Left, VAlign::Top, true, 30);
+    let (l, t, r, b) = drawn(7).unwrap();
+    let aligned = [
+        (HAlign::Center, VAlign::Middle, (40 - tw) / 2, (30 - th) / 2),
+        (HAlign::Right, VAlign::Bottom, 40 - tw, 30 - th),
+    ];
+    for &(h_align, v_align, dx, dy) in aligned.iter() {
+        reset();
+        boxed("ab", h_align, v_align, true, 30);
+        assert_eq!(drawn(7), Some((l + dx, t + dy, r + dx, b + dy)));
+    }
+}
+
+#[test]
+fn nothing_is_drawn_outside_the_box() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let inside = |(l, t, r, b): (i32, i32, i32, i32), height: i32| {
+        l >= 8 && t >= 8 && r <= 48 && b <= 8 + height
+    };
+    // one line much wider than the box
+    reset();
+    boxed("wide wide wide wide wide", HAlign::Left, VAlign::Top, false, 30);
+    assert!(inside(drawn(7).unwrap(), 30));
+    // a box shorter than a line, so bottom alignment starts above it
+    reset();
+    boxed("ab", HAlign::Right, VAlign::Bottom, false, 3);
+    assert!(drawn(7).map_or(true, |bounds| inside(bounds, 3)));
+    let clip = get_clip();
+    assert_eq!((clip.l, clip.t, clip.r, clip.b), (0, 0, SIZE as i32, SIZE as i32));
+}
+
+#[test]
+fn mixed_sizes_share_a_baseline() {
+    let _lock = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    // a block glyph that sits on the baseline, 6 pixels down an 8 pixel line
+    let mut font = Font::new(8, 6, 4);
+    font.add_glyph('x', Glyph::from_rows(&[0b111; 6], 3, 4));
+    let font = register_font(font);
+    reset();
+    prnt("{size:2}x{c:8}{size:1}x", font, 0, 0, 1, 1, 7);
+    let (_, big_top, _, big_bottom) = drawn(7).unwrap();
+    let (_, small_top, _, small_bottom) = drawn(8).unwrap();
+    assert_eq!((big_top, big_bottom), (0, 12));
+    assert_eq!((small_top, small_bottom), (6, 12));
+}
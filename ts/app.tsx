@@ -1,5 +1,5 @@
 import React from 'react'
-import { Route } from 'react-router-dom'
+import { Route, Switch, RouteComponentProps } from 'react-router-dom'
 import { WasmContext, unwrapContextValue } from './wasmContext'
 import Sketch from './sketch'
 import Directory from './directory'
@@ -7,27 +7,21 @@ import Directory from './directory'
 export default class App extends React.Component {
 	static contextType = WasmContext
 	context!: React.ContextType<typeof WasmContext>
-	render() {
-		const { sketches } = unwrapContextValue(this.context)
-		const routes = []
-		for (let i = 0; i < sketches.length; i++) {
-			const sketch = sketches[i]
-			routes.push(
-				<Route
-					key={`/${sketch.url}`}
-					path={`/${sketch.url}`}
-					exact
-					render={props => (
-						<Sketch key={sketch.index} sketch={sketch} {...props} />
-					)}
-				/>
-			)
+	renderSketch = (props: RouteComponentProps<{ url: string }>) => {
+		const { module, sketches } = unwrapContextValue(this.context)
+		const index = module.find_sketch(props.match.params.url)
+		if (index < 0) {
+			return <p>Sketch Not Found</p>
 		}
+		const sketch = sketches[index]
+		return <Sketch key={sketch.index} sketch={sketch} {...props} />
+	}
+	render() {
 		return (
-			<React.Fragment>
+			<Switch>
 				<Route path="/" exact component={Directory} />
-				{routes}
-			</React.Fragment>
+				<Route path="/:url" exact render={this.renderSketch} />
+			</Switch>
 		)
 	}
 }
@@ -1,17 +1,54 @@
 import React from 'react'
 import { Link } from 'react-router-dom'
-import { WasmContext, unwrapContextValue } from './wasmContext'
+import {
+	WasmContext,
+	WasmModule,
+	SketchDescription,
+	unwrapContextValue,
+} from './wasmContext'
+
+function thumbnailUrl(
+	module: WasmModule,
+	{ index, hasThumbnail }: SketchDescription
+) {
+	const png = hasThumbnail ? module.get_sketch_thumbnail(index) : undefined
+	if (png == null) {
+		return null
+	}
+	return URL.createObjectURL(new Blob([png], { type: 'image/png' }))
+}
 
 export default class Directory extends React.Component<{}, {}> {
 	static contextType = WasmContext
 	context!: React.ContextType<typeof WasmContext>
+	thumbnails: Map<number, string | null> = new Map()
+
+	thumbnail(module: WasmModule, sketch: SketchDescription) {
+		if (!this.thumbnails.has(sketch.index)) {
+			this.thumbnails.set(sketch.index, thumbnailUrl(module, sketch))
+		}
+		return this.thumbnails.get(sketch.index)
+	}
+
+	componentWillUnmount() {
+		this.thumbnails.forEach(url => url != null && URL.revokeObjectURL(url))
+		this.thumbnails.clear()
+	}
+
 	render() {
-		const { sketches } = unwrapContextValue(this.context)
-		const links = sketches.map(({ url, name }) => (
-			<li key={url}>
-				<Link to={url}>{name}</Link>
-			</li>
-		))
+		const { module, sketches } = unwrapContextValue(this.context)
+		const links = sketches.map(sketch => {
+			const { url, name, description, tags } = sketch
+			const thumbnail = this.thumbnail(module, sketch)
+			return (
+				<li key={url}>
+					{thumbnail != null && <img src={thumbnail} alt="" />}
+					<Link to={url}>{name}</Link>
+					{description && <p>{description}</p>}
+					{tags.length > 0 && <small>{tags.join(', ')}</small>}
+				</li>
+			)
+		})
 		return <ul>{links}</ul>
 	}
 }
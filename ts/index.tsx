@@ -5,22 +5,9 @@ import ReactDOM from 'react-dom'
 import { BrowserRouter as Router, Route, Link } from 'react-router-dom'
 import { WasmContext, WasmModule, SketchDescription } from './wasmContext'
 import App from './app'
-import { uw } from './utils'
 
 function getSketches(module: WasmModule): ReadonlyArray<SketchDescription> {
-	const count = module.get_sketch_count()
-	const sketches: Array<SketchDescription> = []
-	for (let i = 0; i < count; i++) {
-		sketches[i] = {
-			index: i,
-			name: module.get_sketch_name(i),
-			url: module.get_sketch_url(i),
-			isDesktop: uw(module.get_sketch_is_desktop(i)),
-			isMobile: uw(module.get_sketch_is_mobile(i)),
-			isPublic: uw(module.get_sketch_is_public(i)),
-		}
-	}
-	return sketches
+	return JSON.parse(module.get_sketch_catalog())
 }
 
 import('../crate/pkg/rust_webpack').then(module => {
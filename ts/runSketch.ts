@@ -143,57 +143,70 @@ export default (
 	canvas.addEventListener('wheel', e => {
 		e.preventDefault()
 		e.stopPropagation()
-		let delta = e.deltaY
-		if (e.ctrlKey) {
-			delta *= -1
-		}
-		module.set_wheel(delta)
+		module.set_wheel(e.deltaX, e.deltaY, e.deltaZ, e.deltaMode, e.ctrlKey)
 	})
 
-	const touchState = Array(10)
-	touchState.fill(null)
+	// slots are assigned in rust, keyed by the browser's pointerId
+	function setPointerInfo(e: PointerEvent) {
+		module.set_pointer_info(
+			e.pointerId,
+			e.pressure,
+			e.tiltX,
+			e.tiltY,
+			e.twist || 0,
+			e.width / dim,
+			e.height / dim,
+			e.pointerType
+		)
+	}
+
+	// kinds match PointerEventKind: 0 move, 1 down, 2 up
+	function pushPointerEvent(kind: number, e: PointerEvent) {
+		const { x, y } = getPointerPos(e)
+		module.push_pointer_event(
+			e.pointerId,
+			kind,
+			x,
+			y,
+			e.buttons,
+			e.pressure,
+			e.timeStamp
+		)
+	}
 
 	canvas.addEventListener('pointerenter', e => {
-		const { pointerId } = e
-		const index = touchState.indexOf(null)
-		if (index >= 0) {
-			touchState[index] = pointerId
-			const { x, y } = getPointerPos(e)
-			module.set_pointer_pos(index, x, y)
+		const { x, y } = getPointerPos(e)
+		if (module.begin_pointer(e.pointerId, x, y, e.timeStamp) >= 0) {
+			setPointerInfo(e)
 		}
 	})
 
-	canvas.addEventListener('pointerout', e => {
-		const { pointerId } = e
-		const index = touchState.indexOf(pointerId)
-		if (index >= 0) {
-			touchState[index] = null
-			module.set_pointer_end(index)
-		}
-	})
+	function endPointer(e: PointerEvent) {
+		module.end_pointer(e.pointerId, e.timeStamp)
+	}
+	canvas.addEventListener('pointerout', endPointer)
+	canvas.addEventListener('pointercancel', endPointer)
 
 	canvas.addEventListener('pointerdown', e => {
-		const { pointerId } = e
-		const index = touchState.indexOf(pointerId)
-		if (index >= 0) {
-			module.set_pointer_state(index, e.buttons)
-		}
+		module.set_pointer_state(e.pointerId, e.buttons)
+		setPointerInfo(e)
+		pushPointerEvent(1, e)
 	})
 
 	canvas.addEventListener('pointerup', e => {
-		const { pointerId } = e
-		const index = touchState.indexOf(pointerId)
-		if (index >= 0) {
-			module.set_pointer_state(index, e.buttons)
-		}
+		module.set_pointer_state(e.pointerId, e.buttons)
+		setPointerInfo(e)
+		pushPointerEvent(2, e)
 	})
 
 	canvas.addEventListener('pointermove', e => {
-		const { pointerId } = e
-		const index = touchState.indexOf(pointerId)
-		if (index >= 0) {
-			const { x, y } = getPointerPos(e)
-			module.set_pointer_pos(index, x, y)
+		const { x, y } = getPointerPos(e)
+		module.set_pointer_pos(e.pointerId, x, y)
+		setPointerInfo(e)
+		// every sample the browser merged into this event, oldest first
+		const events = e.getCoalescedEvents ? e.getCoalescedEvents() : []
+		for (const coalesced of events.length > 0 ? events : [e]) {
+			pushPointerEvent(0, coalesced)
 		}
 	})
 
@@ -283,19 +296,50 @@ export default (
 		module.palette_ptr(),
 		paletteSize
 	)
-	function updateTextures() {
+	let screenAllocated = false
+	function updateScreenTexture() {
+		const screen = getScreen(memory.buffer, module.screen_ptr(), screenSize)
 		gl.bindTexture(gl.TEXTURE_2D, screenTexture)
-		gl.texImage2D(
+		if (!screenAllocated) {
+			screenAllocated = true
+			gl.texImage2D(
+				gl.TEXTURE_2D,
+				0,
+				gl.LUMINANCE,
+				screenWidth,
+				screenHeight,
+				0,
+				gl.LUMINANCE,
+				gl.UNSIGNED_BYTE,
+				screen
+			)
+			module.reset_dirty_rect()
+			return
+		}
+		const dirty = module.dirty_rect()
+		if (dirty.length === 0) {
+			return
+		}
+		// WebGL1 has no UNPACK_ROW_LENGTH, so upload the full-width rows
+		// covering the dirty box rather than the box itself
+		const top = dirty[1]
+		const bottom = dirty[3]
+		gl.texSubImage2D(
 			gl.TEXTURE_2D,
 			0,
-			gl.LUMINANCE,
-			screenWidth,
-			screenHeight,
 			0,
+			top,
+			screenWidth,
+			bottom - top,
 			gl.LUMINANCE,
 			gl.UNSIGNED_BYTE,
-			getScreen(memory.buffer, module.screen_ptr(), screenSize)
+			screen.subarray(top * screenWidth, bottom * screenWidth)
 		)
+		module.reset_dirty_rect()
+	}
+
+	function updateTextures() {
+		updateScreenTexture()
 		gl.bindTexture(gl.TEXTURE_2D, swapTexture)
 		gl.texImage2D(
 			gl.TEXTURE_2D,
@@ -2,6 +2,8 @@ import React from 'react'
 import { uw } from './utils'
 
 export type WasmModule = typeof import('../crate/pkg/rust_webpack')
+export type SketchInput = 'pointer' | 'multitouch' | 'wheel'
+// the shape of SketchDescriptor::to_json in crate/src/sketch.rs
 export interface SketchDescription {
 	index: number
 	name: string
@@ -9,6 +11,13 @@ export interface SketchDescription {
 	isDesktop: boolean
 	isMobile: boolean
 	isPublic: boolean
+	description: string
+	author: string
+	tags: ReadonlyArray<string>
+	date: string | null
+	resolution: { width: number; height: number } | null
+	inputs: ReadonlyArray<SketchInput>
+	hasThumbnail: boolean
 }
 interface ContextValue {
 	module: WasmModule